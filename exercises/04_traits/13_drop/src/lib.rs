@@ -1,22 +1,122 @@
 // TODO: implement a so-called "Drop bomb": a type that panics when dropped
 //  unless a certain operation has been performed on it.
 //  You can see the expected API in the tests below.
-struct DropBomb {
-    fuse: bool,
+use std::mem::ManuallyDrop;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+// Carries a `T` that can only be had back by defusing the bomb.
+pub struct DropBomb<T> {
+    payload: T,
+    // What the panic says if the bomb goes off.
+    message: String,
+}
+impl<T> DropBomb<T> {
+    pub fn new(value: T) -> Self {
+        DropBomb::with_message(value, "Bomb dropped!")
+    }
+
+    pub fn with_message(value: T, msg: impl Into<String>) -> Self {
+        DropBomb {
+            payload: value,
+            message: msg.into(),
+        }
+    }
+
+    // Takes the bomb apart without running `Drop`, so it can't go off.
+    pub fn defuse(self) -> T {
+        let mut bomb = ManuallyDrop::new(self);
+        drop(std::mem::take(&mut bomb.message));
+        // SAFETY: `bomb` is never dropped or used again, so the payload is
+        // moved out exactly once.
+        unsafe { std::ptr::read(&bomb.payload) }
+    }
+}
+impl<T> Drop for DropBomb<T> {
+    // The payload is still dropped while the panic unwinds.
+    fn drop(&mut self) {
+        panic!("{}", self.message);
+    }
+}
+
+// A bomb whose clones can be handed to other threads. Defusing any clone
+// defuses them all; otherwise only the last clone to be dropped goes off.
+#[derive(Clone)]
+pub struct SharedDropBomb {
+    // Always `Some` until the bomb is dropped.
+    fuse: Option<Arc<Fuse>>,
+}
+// What every clone of a `SharedDropBomb` points at.
+struct Fuse {
+    defused: AtomicBool,
+    // What the panic says if the bomb goes off.
+    message: String,
+}
+impl SharedDropBomb {
+    pub fn new() -> Self {
+        SharedDropBomb::with_message("Bomb dropped!")
+    }
+
+    pub fn with_message(msg: impl Into<String>) -> Self {
+        SharedDropBomb {
+            fuse: Some(Arc::new(Fuse {
+                defused: AtomicBool::new(false),
+                message: msg.into(),
+            })),
+        }
+    }
+
+    // Like `AtomicBool::swap`, reports what it found: true if this call did
+    // the defusing, false if some clone had already defused the bomb.
+    pub fn defuse(&self) -> bool {
+        self.fuse
+            .as_ref()
+            .is_some_and(|fuse| !fuse.defused.swap(true, Ordering::AcqRel))
+    }
+
+    pub fn is_defused(&self) -> bool {
+        self.fuse
+            .as_ref()
+            .is_some_and(|fuse| fuse.defused.load(Ordering::Acquire))
+    }
 }
-impl DropBomb {
-    fn new() -> Self {
-        DropBomb { fuse: false }
+impl Default for SharedDropBomb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Drop for SharedDropBomb {
+    fn drop(&mut self) {
+        // `into_inner` only succeeds for the last clone, even when several are
+        // dropped at once on different threads.
+        let last = self.fuse.take().and_then(Arc::into_inner);
+        if let Some(fuse) = last.filter(|fuse| !fuse.defused.load(Ordering::Acquire)) {
+            panic!("{}", fuse.message);
+        }
+    }
+}
+
+// The other way round from `DropBomb`: runs `F` when dropped, unless it
+// was cancelled first.
+pub struct DropGuard<F: FnOnce()> {
+    // Only `None` once the closure has been run or cancelled.
+    cleanup: Option<F>,
+}
+impl<F: FnOnce()> DropGuard<F> {
+    pub fn new(cleanup: F) -> Self {
+        DropGuard {
+            cleanup: Some(cleanup),
+        }
     }
 
-    fn defuse(&mut self) {
-        self.fuse = true;
+    pub fn cancel(mut self) {
+        self.cleanup = None;
     }
 }
-impl Drop for DropBomb {
+impl<F: FnOnce()> Drop for DropGuard<F> {
     fn drop(&mut self) {
-        if !self.fuse {
-            panic!("Bomb dropped!");
+        if let Some(cleanup) = self.cleanup.take() {
+            cleanup();
         }
     }
 }
@@ -24,19 +124,125 @@ impl Drop for DropBomb {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::cell::Cell;
 
     #[test]
     #[should_panic]
     fn test_drop_bomb() {
-        let bomb = DropBomb::new();
+        let _bomb = DropBomb::new(());
         // The bomb should panic when dropped
     }
 
+    #[test]
+    #[should_panic(expected = "Bomb dropped!")]
+    fn test_default_message() {
+        let _bomb = DropBomb::new(());
+    }
+
+    #[test]
+    #[should_panic(expected = "unsaved changes in the settings form")]
+    fn test_custom_message() {
+        let _bomb = DropBomb::with_message((), "unsaved changes in the settings form");
+    }
+
     #[test]
     fn test_defused_drop_bomb() {
-        let mut bomb = DropBomb::new();
+        let bomb = DropBomb::new(());
         bomb.defuse();
         // The bomb should not panic when dropped
         // since it has been defused
     }
+
+    #[test]
+    fn test_defuse_returns_payload() {
+        let bomb = DropBomb::new(String::from("connection #7"));
+        assert_eq!(bomb.defuse(), "connection #7");
+    }
+
+    #[test]
+    #[should_panic(expected = "Bomb dropped!")]
+    fn test_undefused_payload_bomb() {
+        let _bomb = DropBomb::new(String::from("connection #7"));
+    }
+
+    #[test]
+    fn test_guard_runs_on_drop() {
+        let ran = Cell::new(false);
+        {
+            let _guard = DropGuard::new(|| ran.set(true));
+            assert!(!ran.get());
+        }
+        assert!(ran.get());
+    }
+
+    #[test]
+    fn test_cancelled_guard_does_not_run() {
+        let ran = Cell::new(false);
+        let guard = DropGuard::new(|| ran.set(true));
+        guard.cancel();
+        assert!(!ran.get());
+    }
+
+    #[test]
+    fn test_shared_bomb_defused_from_another_thread() {
+        let bomb = SharedDropBomb::new();
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let bomb = bomb.clone();
+                std::thread::spawn(move || {
+                    if i == 2 {
+                        bomb.defuse();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        drop(bomb);
+    }
+
+    #[test]
+    fn test_shared_bomb_is_defused() {
+        let bomb = SharedDropBomb::new();
+        let clone = bomb.clone();
+        assert!(!bomb.is_defused());
+        clone.defuse();
+        assert!(bomb.is_defused());
+        assert!(clone.is_defused());
+    }
+
+    #[test]
+    fn test_shared_defuse_reports_first_call() {
+        let bomb = SharedDropBomb::new();
+        let clone = bomb.clone();
+        assert!(bomb.defuse());
+        assert!(!clone.defuse());
+        assert!(!bomb.defuse());
+    }
+
+    #[test]
+    #[should_panic(expected = "Bomb dropped!")]
+    fn test_shared_bomb_goes_off_with_last_clone() {
+        let bomb = SharedDropBomb::new();
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let bomb = bomb.clone();
+                std::thread::spawn(move || drop(bomb))
+            })
+            .collect();
+        // None of the other clones was the last one.
+        for handle in handles {
+            assert!(handle.join().is_ok());
+        }
+        drop(bomb);
+    }
+    #[test]
+    #[should_panic(expected = "worker pool shut down without a handshake")]
+    fn test_shared_custom_message() {
+        let bomb = SharedDropBomb::with_message("worker pool shut down without a handshake");
+        let clone = bomb.clone();
+        drop(bomb);
+        drop(clone);
+    }
 }
@@ -8,23 +8,210 @@
 //   It should be possible to print its debug representation.
 //
 // Tests are located in the `tests` folder—pay attention to the visibility of your types and methods.
-use std::ops::Add;
-#[derive(Debug, Clone, Copy)]
-pub struct SaturatingU16 {
-    value: u16,
-}
-impl From<u16> for SaturatingU16 {
-    fn from(n: u16) -> SaturatingU16 {
-        SaturatingU16 { value: n }
-    }
-}
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Sub};
 
-impl From<&u16> for SaturatingU16 {
-    fn from(n: &u16) -> SaturatingU16 {
-        SaturatingU16 { value: *n }
-    }
+// Defines `$name`, a wrapper around the unsigned integer `$inner` whose
+// arithmetic clamps at `$inner::MIN` and `$inner::MAX` instead of
+// overflowing, along with its conversions, comparisons and operators.
+macro_rules! saturating_type {
+    ($name:ident, $inner:ident) => {
+        #[derive(Debug, Clone, Copy, Eq)]
+        pub struct $name {
+            value: $inner,
+        }
+        impl $name {
+            // Whether an addition has clamped (or would clamp) at the maximum.
+            pub fn is_saturated(&self) -> bool {
+                self.value == $inner::MAX
+            }
+
+            pub fn get(&self) -> $inner {
+                self.value
+            }
+
+            // For when overflowing is a bug rather than something to clamp away.
+            pub fn checked_add(self, rhs: impl Into<$name>) -> Option<$name> {
+                self.value.checked_add(rhs.into().value).map($name::from)
+            }
+        }
+
+        impl From<$inner> for $name {
+            fn from(n: $inner) -> $name {
+                $name { value: n }
+            }
+        }
+
+        impl From<&$inner> for $name {
+            fn from(n: &$inner) -> $name {
+                $name { value: *n }
+            }
+        }
+
+        impl From<$name> for $inner {
+            fn from(n: $name) -> $inner {
+                n.value
+            }
+        }
+
+        impl PartialEq<$inner> for $name {
+            fn eq(&self, other: &$inner) -> bool {
+                self.value.eq(other)
+            }
+        }
+
+        impl PartialOrd<$inner> for $name {
+            fn partial_cmp(&self, other: &$inner) -> Option<Ordering> {
+                Some(self.value.cmp(other))
+            }
+        }
+        impl PartialEq<$name> for $name {
+            fn eq(&self, other: &$name) -> bool {
+                self.value.eq(&other.value)
+            }
+        }
+
+        impl PartialOrd<$name> for $name {
+            fn partial_cmp(&self, other: &$name) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &$name) -> Ordering {
+                self.value.cmp(&other.value)
+            }
+        }
+
+        // Has to agree with `PartialEq`, so it only looks at the value too.
+        impl Hash for $name {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.value.hash(state);
+            }
+        }
+
+        // Just the number, honouring width and padding like the plain
+        // integer does.
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.value, f)
+            }
+        }
+
+        impl Add<$inner> for $name {
+            type Output = $inner;
+            fn add(self, rhs: $inner) -> Self::Output {
+                self.value.saturating_add(rhs)
+            }
+        }
+        impl Add<&$name> for $name {
+            type Output = $name;
+            fn add(self, rhs: &$name) -> Self::Output {
+                $name {
+                    value: self.value.saturating_add(rhs.value),
+                }
+            }
+        }
+
+        impl Add<$name> for $name {
+            type Output = $name;
+            fn add(self, rhs: $name) -> Self::Output {
+                $name {
+                    value: self.value.saturating_add(rhs.value),
+                }
+            }
+        }
+
+        impl AddAssign<$inner> for $name {
+            fn add_assign(&mut self, rhs: $inner) {
+                self.value = self.value.saturating_add(rhs);
+            }
+        }
+
+        impl AddAssign<&$name> for $name {
+            fn add_assign(&mut self, rhs: &$name) {
+                *self += rhs.value;
+            }
+        }
+
+        impl AddAssign<$name> for $name {
+            fn add_assign(&mut self, rhs: $name) {
+                *self += rhs.value;
+            }
+        }
+
+        // Subtraction mirrors addition, clamping at zero instead.
+        impl Sub<$inner> for $name {
+            type Output = $inner;
+            fn sub(self, rhs: $inner) -> Self::Output {
+                self.value.saturating_sub(rhs)
+            }
+        }
+        impl Sub<&$name> for $name {
+            type Output = $name;
+            fn sub(self, rhs: &$name) -> Self::Output {
+                $name {
+                    value: self.value.saturating_sub(rhs.value),
+                }
+            }
+        }
+
+        impl Sub<$name> for $name {
+            type Output = $name;
+            fn sub(self, rhs: $name) -> Self::Output {
+                $name {
+                    value: self.value.saturating_sub(rhs.value),
+                }
+            }
+        }
+
+        impl Mul<$inner> for $name {
+            type Output = $inner;
+            fn mul(self, rhs: $inner) -> Self::Output {
+                self.value.saturating_mul(rhs)
+            }
+        }
+        impl Mul<&$name> for $name {
+            type Output = $name;
+            fn mul(self, rhs: &$name) -> Self::Output {
+                $name {
+                    value: self.value.saturating_mul(rhs.value),
+                }
+            }
+        }
+
+        impl Mul<$name> for $name {
+            type Output = $name;
+            fn mul(self, rhs: $name) -> Self::Output {
+                $name {
+                    value: self.value.saturating_mul(rhs.value),
+                }
+            }
+        }
+
+        // Both start from zero, so an empty iterator sums to zero.
+        impl Sum<$name> for $name {
+            fn sum<I: Iterator<Item = $name>>(iter: I) -> Self {
+                iter.fold($name { value: 0 }, |sum, item| sum + item)
+            }
+        }
+
+        impl<'a> Sum<&'a $name> for $name {
+            fn sum<I: Iterator<Item = &'a $name>>(iter: I) -> Self {
+                iter.fold($name { value: 0 }, |sum, item| sum + item)
+            }
+        }
+    };
 }
 
+saturating_type!(SaturatingU8, u8);
+saturating_type!(SaturatingU16, u16);
+saturating_type!(SaturatingU32, u32);
+saturating_type!(SaturatingU64, u64);
+
 impl From<u8> for SaturatingU16 {
     fn from(n: u8) -> SaturatingU16 {
         SaturatingU16 { value: n as u16 }
@@ -37,43 +224,37 @@ impl From<&u8> for SaturatingU16 {
     }
 }
 
-impl PartialEq<u16> for SaturatingU16 {
-    fn eq(&self, other: &u16) -> bool {
-        self.value.eq(other)
-    }
-}
-
-impl PartialOrd<u16> for SaturatingU16 {
-    fn partial_cmp(&self, other: &u16) -> Option<std::cmp::Ordering> {
-        Some(self.value.cmp(other))
-    }
-}
-impl PartialEq<SaturatingU16> for SaturatingU16 {
-    fn eq(&self, other: &SaturatingU16) -> bool {
-        self.value.eq(&other.value)
+// Wider inputs are clamped into range rather than rejected.
+impl From<u32> for SaturatingU16 {
+    fn from(n: u32) -> SaturatingU16 {
+        SaturatingU16 {
+            value: u16::try_from(n).unwrap_or(u16::MAX),
+        }
     }
 }
 
-impl Add<u16> for SaturatingU16 {
-    type Output = u16;
-    fn add(self, rhs: u16) -> Self::Output {
-        self.value.saturating_add(rhs)
-    }
-}
-impl Add<&SaturatingU16> for SaturatingU16 {
-    type Output = SaturatingU16;
-    fn add(self, rhs: &SaturatingU16) -> Self::Output {
+impl From<i32> for SaturatingU16 {
+    fn from(n: i32) -> SaturatingU16 {
         SaturatingU16 {
-            value: self.value.saturating_add(rhs.value),
+            value: n.clamp(0, u16::MAX as i32) as u16,
         }
     }
 }
 
-impl Add<SaturatingU16> for SaturatingU16 {
-    type Output = SaturatingU16;
-    fn add(self, rhs: SaturatingU16) -> Self::Output {
-        SaturatingU16 {
-            value: self.value.saturating_add(rhs.value),
-        }
+// `.saturating_sum()` on any iterator of things that convert into a
+// `SaturatingU16` (`u16`, `u8`, `SaturatingU16`, ...), clamping at `u16::MAX`.
+pub trait SaturatingSumExt: Iterator + Sized
+where
+    Self::Item: Into<SaturatingU16>,
+{
+    fn saturating_sum(self) -> SaturatingU16 {
+        self.fold(SaturatingU16::from(0u16), |sum, item| sum + item.into())
     }
 }
+
+impl<I> SaturatingSumExt for I
+where
+    I: Iterator,
+    I::Item: Into<SaturatingU16>,
+{
+}
@@ -1,4 +1,5 @@
-use outro_03::SaturatingU16;
+use outro_03::{SaturatingSumExt, SaturatingU16, SaturatingU64, SaturatingU8};
+use std::collections::{BTreeMap, HashSet};
 
 #[test]
 fn test_saturating_u16() {
@@ -15,3 +16,170 @@ fn test_saturating_u16() {
     assert_eq!(a + 5u16, 15u16);
     assert_eq!(a + e, SaturatingU16::from(u16::MAX));
 }
+
+#[test]
+fn test_is_saturated() {
+    let max: SaturatingU16 = u16::MAX.into();
+    let below: SaturatingU16 = (u16::MAX - 1).into();
+
+    assert!(max.is_saturated());
+    assert!(!below.is_saturated());
+    assert!(!SaturatingU16::from(0u16).is_saturated());
+    assert!((below + max).is_saturated());
+}
+
+#[test]
+fn test_saturating_sum() {
+    assert_eq!([1u16, 2, 3].into_iter().saturating_sum(), 6u16);
+    assert_eq!(std::iter::empty::<u16>().saturating_sum(), 0u16);
+    assert_eq!(
+        (0..100000u32).map(|_| 1u16).saturating_sum(),
+        SaturatingU16::from(u16::MAX)
+    );
+    let values = [SaturatingU16::from(u16::MAX), SaturatingU16::from(1u16)];
+    assert!(values.into_iter().saturating_sum().is_saturated());
+}
+
+#[test]
+fn test_saturating_sub() {
+    let a: SaturatingU16 = 10u16.into();
+    let b: SaturatingU16 = 3u16.into();
+    let c = &b;
+
+    assert_eq!(a - b, SaturatingU16::from(7u16));
+    assert_eq!(a - c, 7u16);
+    assert_eq!(a - 4u16, 6u16);
+    assert_eq!(b - a, SaturatingU16::from(0u16));
+    assert_eq!(b - c, 0u16);
+    assert_eq!(SaturatingU16::from(3u16) - 10u16, 0u16);
+}
+
+#[test]
+fn test_saturating_mul() {
+    let a: SaturatingU16 = 40000u16.into();
+    let b: SaturatingU16 = 2u16.into();
+    let c = &b;
+
+    assert_eq!(a * 2u16, 65535u16);
+    assert_eq!(a * b, SaturatingU16::from(u16::MAX));
+    assert_eq!(b * 300u16, 600u16);
+    assert_eq!(b * b, SaturatingU16::from(4u16));
+    assert_eq!(b * c, 4u16);
+    assert!((a * c).is_saturated());
+}
+
+#[test]
+fn test_saturating_add_assign() {
+    let step: SaturatingU16 = 20000u16.into();
+    let mut total = SaturatingU16::from(0u16);
+    for _ in 0..5 {
+        total += step;
+    }
+    assert_eq!(total, 65535u16);
+
+    total += 1u16;
+    total += &step;
+    assert_eq!(total, SaturatingU16::from(u16::MAX));
+
+    let mut small = SaturatingU16::from(1u16);
+    small += 2u16;
+    small += &step;
+    assert_eq!(small, 20003u16);
+}
+
+#[test]
+fn test_sum() {
+    let readings: Vec<SaturatingU16> = [30000u16, 30000, 30000]
+        .into_iter()
+        .map(SaturatingU16::from)
+        .collect();
+    assert_eq!(readings.iter().sum::<SaturatingU16>(), 65535u16);
+    assert!(readings.into_iter().sum::<SaturatingU16>().is_saturated());
+
+    let small = [SaturatingU16::from(1u16), SaturatingU16::from(2u16)];
+    assert_eq!(small.iter().sum::<SaturatingU16>(), 3u16);
+    assert_eq!(
+        std::iter::empty::<SaturatingU16>().sum::<SaturatingU16>(),
+        SaturatingU16::from(0u16)
+    );
+}
+
+#[test]
+fn test_into_u16() {
+    assert_eq!(u16::from(SaturatingU16::from(7u16)), 7);
+    assert_eq!(SaturatingU16::from(7u16).get(), 7);
+    let one = SaturatingU16::from(1u16);
+    let max: u16 = (SaturatingU16::from(u16::MAX) + one).into();
+    assert_eq!(max, u16::MAX);
+}
+
+#[test]
+fn test_clamping_conversions() {
+    assert_eq!(SaturatingU16::from(70000u32), u16::MAX);
+    assert_eq!(SaturatingU16::from(1234u32), 1234u16);
+    assert_eq!(SaturatingU16::from(-5i32), 0u16);
+    assert_eq!(SaturatingU16::from(100000i32), u16::MAX);
+    assert_eq!(SaturatingU16::from(1234i32), 1234u16);
+}
+
+#[test]
+fn test_checked_add() {
+    let a: SaturatingU16 = 65000u16.into();
+
+    assert_eq!(a.checked_add(535u16), Some(SaturatingU16::from(u16::MAX)));
+    assert_eq!(a.checked_add(536u16), None);
+    assert_eq!(a.checked_add(a), None);
+    assert_eq!(
+        SaturatingU16::from(1u16).checked_add(2u8),
+        Some(SaturatingU16::from(3u16))
+    );
+}
+
+#[test]
+fn test_ordering_and_display() {
+    let mut values: Vec<SaturatingU16> = [300u16, 7, u16::MAX, 42]
+        .into_iter()
+        .map(SaturatingU16::from)
+        .collect();
+    values.sort();
+    assert_eq!(values, [7u16, 42, 300, u16::MAX].map(SaturatingU16::from));
+    assert!(SaturatingU16::from(1u16) < SaturatingU16::from(2u16));
+    assert!(SaturatingU16::from(3u16) > 2u16);
+
+    let counts = BTreeMap::from([(SaturatingU16::from(2u16), "b"), (1u16.into(), "a")]);
+    assert_eq!(counts.values().copied().collect::<Vec<_>>(), ["a", "b"]);
+    let unique: HashSet<SaturatingU16> = [1u16, 1, 2].into_iter().map(Into::into).collect();
+    assert_eq!(unique.len(), 2);
+
+    assert_eq!(SaturatingU16::from(42u16).to_string(), "42");
+    assert_eq!(format!("{:>4}|", SaturatingU16::from(7u16)), "   7|");
+}
+
+#[test]
+fn test_saturating_u8() {
+    let a: SaturatingU8 = 200u8.into();
+    let b: SaturatingU8 = (&100u8).into();
+
+    assert_eq!(a + b, SaturatingU8::from(255u8));
+    assert!((a + b).is_saturated());
+    assert_eq!(a + 55u8, 255u8);
+    assert_eq!(b + b, 200u8);
+    assert_eq!(b - a, 0u8);
+    assert_eq!(b * 3u8, 255u8);
+    assert_eq!(a.checked_add(56u8), None);
+    assert!(b < a && a > 100u8);
+
+    let mut total = SaturatingU8::from(0u8);
+    for _ in 0..10 {
+        total += 30u8;
+    }
+    assert_eq!(u8::from(total), 255);
+    assert_eq!([a, b].iter().sum::<SaturatingU8>().to_string(), "255");
+}
+
+#[test]
+fn test_saturating_u64() {
+    let max = SaturatingU64::from(u64::MAX);
+    assert_eq!(max + SaturatingU64::from(1u64), max);
+    assert_eq!(SaturatingU64::from(2u64) * 3u64, 6u64);
+}
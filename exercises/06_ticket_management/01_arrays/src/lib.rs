@@ -1,9 +1,109 @@
 // TODO: Flesh out the `WeekTemperatures` struct and its method implementations to pass the tests.
 
-pub struct WeekTemperatures {
-    tempratures: [Option<i32>;7]
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Sub};
+
+// Weekday names, in the same Monday-first order as the temperature slots.
+const WEEKDAY_NAMES: [&str; Weekday::COUNT] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnknownWeekday(pub String);
+
+impl fmt::Display for UnknownWeekday {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` is not a weekday name", self.0)
+    }
+}
+
+impl std::error::Error for UnknownWeekday {}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CsvRowError {
+    // How many fields the row actually had.
+    FieldCount(usize),
+    InvalidTemperature(String),
+}
+
+impl fmt::Display for CsvRowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvRowError::FieldCount(count) => {
+                write!(
+                    f,
+                    "expected {} comma-separated fields, found {}",
+                    Weekday::COUNT,
+                    count
+                )
+            }
+            CsvRowError::InvalidTemperature(field) => {
+                write!(f, "`{}` is not a temperature", field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CsvRowError {}
+
+// A temperature slot index, only constructible in `0..7`, so indexing
+// the slots can't go out of bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DayIndex(usize);
+
+#[derive(Debug, PartialEq, Eq)]
+struct DayIndexOutOfRange(usize);
+
+impl DayIndex {
+    fn new(index: usize) -> Result<Self, DayIndexOutOfRange> {
+        if index < Weekday::COUNT {
+            Ok(DayIndex(index))
+        } else {
+            Err(DayIndexOutOfRange(index))
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        let index = WEEKDAY_NAMES.iter().position(|day| *day == name)?;
+        DayIndex::new(index).ok()
+    }
+
+    fn get(self) -> usize {
+        self.0
+    }
+}
+
+impl From<Weekday> for DayIndex {
+    fn from(day: Weekday) -> Self {
+        DayIndex::new(day.index()).expect("there are seven weekdays")
+    }
 }
 
+impl From<DayIndex> for Weekday {
+    fn from(index: DayIndex) -> Self {
+        Weekday::all()[index.get()]
+    }
+}
+
+// Generic over the reading type, so `f32` or `i16` sensors work as well as
+// the default `i32`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct WeekTemperatures<T = i32> {
+    tempratures: [Option<T>; Weekday::COUNT],
+}
+
+pub type WeekTemperaturesI32 = WeekTemperatures<i32>;
+
+// Ordered Monday first, like the temperature slots.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Weekday {
     Monday,
     Tuesday,
@@ -14,36 +114,426 @@ pub enum Weekday {
     Sunday,
 }
 
-impl WeekTemperatures {
+// Invariant: `Weekday::COUNT` is the number of variants, and `index` maps
+// them one-to-one onto `0..COUNT` in declaration order. Every per-day array
+// (`all()`, the names, the temperature slots) is sized by `COUNT`.
+//
+// Adding a variant breaks the exhaustive `match` in `index` until it gets a
+// slot, and `slot` rejects one that doesn't fit until `COUNT` is bumped,
+// which in turn breaks every array literal sized by it until it has an entry
+// for the new day. The assertion below keeps `all()` and `index` in
+// agreement.
+const _: () = {
+    let all = Weekday::all();
+    let mut i = 0;
+    while i < Weekday::COUNT {
+        assert!(all[i].index() == i, "`Weekday::all()` must follow `index`");
+        i += 1;
+    }
+};
+
+const fn slot(index: usize) -> usize {
+    assert!(index < Weekday::COUNT, "bump `Weekday::COUNT`");
+    index
+}
+
+impl Weekday {
+    pub const COUNT: usize = 7;
+
+    // Every day, Monday first.
+    pub const fn all() -> [Weekday; Weekday::COUNT] {
+        [
+            Weekday::Monday,
+            Weekday::Tuesday,
+            Weekday::Wednesday,
+            Weekday::Thursday,
+            Weekday::Friday,
+            Weekday::Saturday,
+            Weekday::Sunday,
+        ]
+    }
+
+    // Position in `Weekday::all()`, which is also the temperature slot.
+    pub const fn index(&self) -> usize {
+        match self {
+            Weekday::Monday => const { slot(0) },
+            Weekday::Tuesday => const { slot(1) },
+            Weekday::Wednesday => const { slot(2) },
+            Weekday::Thursday => const { slot(3) },
+            Weekday::Friday => const { slot(4) },
+            Weekday::Saturday => const { slot(5) },
+            Weekday::Sunday => const { slot(6) },
+        }
+    }
+
+    // The day after, wrapping from Sunday back to Monday.
+    pub fn next(&self) -> Weekday {
+        Weekday::all()[(self.index() + 1) % Weekday::COUNT]
+    }
+
+    // The day before, wrapping from Monday back to Sunday.
+    pub fn previous(&self) -> Weekday {
+        Weekday::all()[(self.index() + Weekday::COUNT - 1) % Weekday::COUNT]
+    }
+
+    pub fn is_weekend(&self) -> bool {
+        matches!(self, Weekday::Saturday | Weekday::Sunday)
+    }
+
+    pub fn is_weekday(&self) -> bool {
+        !self.is_weekend()
+    }
+}
+
+impl<T: Copy> WeekTemperatures<T> {
     pub fn new() -> Self {
-        WeekTemperatures { tempratures: [None;7] }
+        WeekTemperatures {
+            tempratures: [None; Weekday::COUNT],
+        }
     }
 
-    pub fn get_temperature(&self, day: Weekday) -> Option<i32> {
-        match day {
-            Weekday::Monday => self.tempratures[0],
-            Weekday::Tuesday => self.tempratures[1],
-            Weekday::Wednesday => self.tempratures[2],
-            Weekday::Thursday => self.tempratures[3],
-            Weekday::Friday => self.tempratures[4],
-            Weekday::Saturday => self.tempratures[5],
-            Weekday::Sunday => self.tempratures[6]
+    // Every day starts out with its baseline value (Monday first),
+    // which `set_temperature` overrides as usual.
+    pub fn with_defaults(defaults: [T; Weekday::COUNT]) -> Self {
+        WeekTemperatures {
+            tempratures: defaults.map(Some),
         }
     }
 
-    pub fn set_temperature(&mut self, day: Weekday, temperature: i32) {
-        match day {
-            Weekday::Monday => self.tempratures[0] = Some(temperature),
-            Weekday::Tuesday => self.tempratures[1] = Some(temperature),
-            Weekday::Wednesday => self.tempratures[2] = Some(temperature),
-            Weekday::Thursday => self.tempratures[3] = Some(temperature),
-            Weekday::Friday => self.tempratures[4] = Some(temperature),
-            Weekday::Saturday => self.tempratures[5] = Some(temperature),
-            Weekday::Sunday => self.tempratures[6] = Some(temperature)
+    pub fn get_temperature(&self, day: Weekday) -> Option<T> {
+        self.tempratures[DayIndex::from(day).get()]
+    }
+
+    // Like `HashMap::insert`, hands back what was there before, if anything.
+    pub fn set_temperature(&mut self, day: Weekday, temperature: T) -> Option<T> {
+        self.tempratures[DayIndex::from(day).get()].replace(temperature)
+    }
+
+    // Undoes a bad reading, leaving the other days alone.
+    pub fn clear_temperature(&mut self, day: Weekday) {
+        self.tempratures[DayIndex::from(day).get()] = None;
+    }
+
+    pub fn clear_all(&mut self) {
+        self.tempratures = [None; Weekday::COUNT];
+    }
+
+    pub fn recorded_count(&self) -> usize {
+        self.tempratures.iter().filter(|t| t.is_some()).count()
+    }
+
+    // True once every day of the week has a reading.
+    pub fn is_complete(&self) -> bool {
+        self.recorded_count() == Weekday::COUNT
+    }
+
+    // Recorded days, Monday first.
+    pub fn iter(&self) -> impl Iterator<Item = (Weekday, T)> + '_ {
+        self.tempratures
+            .iter()
+            .enumerate()
+            .filter_map(|(index, temperature)| {
+                let day = Weekday::from(DayIndex::new(index).ok()?);
+                temperature.map(|t| (day, t))
+            })
+    }
+
+    // Days recorded in only one week keep that reading; days recorded in
+    // both get `resolver(self, other)`.
+    pub fn merge_with(&mut self, other: &WeekTemperatures<T>, resolver: impl Fn(&T, &T) -> T) {
+        for (mine, theirs) in self.tempratures.iter_mut().zip(other.tempratures.iter()) {
+            *mine = match (*mine, *theirs) {
+                (Some(a), Some(b)) => Some(resolver(&a, &b)),
+                (a, b) => a.or(b),
+            };
         }
     }
 }
 
+impl<T: Copy> Default for WeekTemperatures<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + PartialEq> WeekTemperatures<T> {
+    // For feeds that report a magic value (e.g. -9999) when there's no
+    // reading: `raw == sentinel` leaves the day unrecorded.
+    pub fn set_with_sentinel(&mut self, day: Weekday, raw: T, sentinel: T) {
+        self.tempratures[DayIndex::from(day).get()] = (raw != sentinel).then_some(raw);
+    }
+}
+
+impl<T: Copy + PartialOrd> WeekTemperatures<T> {
+    // Warmest recorded day; ties go to the earlier day.
+    pub fn hottest(&self) -> Option<(Weekday, T)> {
+        self.iter()
+            .reduce(|best, next| if next.1 > best.1 { next } else { best })
+    }
+
+    // Coldest recorded day; ties go to the earlier day.
+    pub fn coldest(&self) -> Option<(Weekday, T)> {
+        self.iter()
+            .reduce(|best, next| if next.1 < best.1 { next } else { best })
+    }
+
+    // Recorded days strictly warmer than `threshold`, Monday first.
+    pub fn days_above(&self, threshold: T) -> Vec<Weekday> {
+        self.iter()
+            .filter(|&(_, t)| t > threshold)
+            .map(|(day, _)| day)
+            .collect()
+    }
+
+    // Recorded days strictly colder than `threshold`, Monday first.
+    pub fn days_below(&self, threshold: T) -> Vec<Weekday> {
+        self.iter()
+            .filter(|&(_, t)| t < threshold)
+            .map(|(day, _)| day)
+            .collect()
+    }
+
+    // Recorded days from `low` to `high`, both included, Monday first.
+    pub fn days_in_range(&self, low: T, high: T) -> Vec<Weekday> {
+        self.iter()
+            .filter(|&(_, t)| low <= t && t <= high)
+            .map(|(day, _)| day)
+            .collect()
+    }
+}
+
+impl<T> WeekTemperatures<T>
+where
+    T: Copy
+        + PartialOrd
+        + From<u8>
+        + Add<Output = T>
+        + Sub<Output = T>
+        + Mul<Output = T>
+        + Div<Output = T>,
+{
+    // Fills each unrecorded day on a straight line between the nearest
+    // recorded days on either side. Days with a reading on one side only
+    // copy it; a week with no readings is left alone. Integer readings
+    // round towards the earlier neighbour.
+    pub fn interpolate_missing(&mut self) {
+        let recorded = self.tempratures;
+        let known = |i: usize| recorded[i].map(|t| (i, t));
+        for (index, slot) in self.tempratures.iter_mut().enumerate() {
+            if slot.is_some() {
+                continue;
+            }
+            let before = (0..index).rev().find_map(known);
+            let after = (index + 1..Weekday::COUNT).find_map(known);
+            *slot = match (before, after) {
+                (Some((i, a)), Some((j, b))) => {
+                    let step = T::from((index - i) as u8);
+                    let span = T::from((j - i) as u8);
+                    // Only ever subtract the smaller reading, so unsigned
+                    // readings can't underflow on a falling stretch.
+                    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+                    // Divide before multiplying so a wide stretch can't
+                    // overflow; the remainder is below `span`, so scaling it
+                    // stays small.
+                    let quotient = (high - low) / span;
+                    let remainder = (high - low) - quotient * span;
+                    let offset = quotient * step + remainder * step / span;
+                    if a <= b {
+                        Some(a + offset)
+                    } else {
+                        Some(a - offset)
+                    }
+                }
+                (Some((_, t)), None) | (None, Some((_, t))) => Some(t),
+                (None, None) => None,
+            };
+        }
+    }
+}
+
+impl<T: Copy + Into<f64>> WeekTemperatures<T> {
+    // Storage stays in Celsius; this only converts on the way out.
+    pub fn get_temperature_fahrenheit(&self, day: Weekday) -> Option<f64> {
+        self.get_temperature(day)
+            .map(|c| c.into() * 9.0 / 5.0 + 32.0)
+    }
+
+    // Average of the recorded Saturday and Sunday temperatures, if any.
+    pub fn weekend_average(&self) -> Option<f64> {
+        let recorded: Vec<f64> = [Weekday::Saturday, Weekday::Sunday]
+            .into_iter()
+            .filter_map(|day| self.get_temperature(day))
+            .map(Into::into)
+            .collect();
+        if recorded.is_empty() {
+            return None;
+        }
+        Some(recorded.iter().sum::<f64>() / recorded.len() as f64)
+    }
+
+    // Mean of every recorded day, `None` if nothing was recorded.
+    pub fn average(&self) -> Option<f64> {
+        let recorded: Vec<f64> = self.iter().map(|(_, t)| t.into()).collect();
+        if recorded.is_empty() {
+            return None;
+        }
+        Some(recorded.iter().sum::<f64>() / recorded.len() as f64)
+    }
+
+    // Population standard deviation of the recorded days, `None` if nothing
+    // was recorded.
+    pub fn std_dev(&self) -> Option<f64> {
+        let mean = self.average()?;
+        let recorded: Vec<f64> = self.iter().map(|(_, t)| t.into()).collect();
+        let variance =
+            recorded.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / recorded.len() as f64;
+        Some(variance.sqrt())
+    }
+}
+
+impl<T: Copy> WeekTemperatures<T> {
+    // Recorded days only, keyed by weekday name (e.g. "Monday").
+    pub fn to_named_map(&self) -> BTreeMap<String, T> {
+        WEEKDAY_NAMES
+            .iter()
+            .zip(self.tempratures.iter())
+            .filter_map(|(name, temperature)| temperature.map(|t| (name.to_string(), t)))
+            .collect()
+    }
+
+    pub fn from_named_map(map: &BTreeMap<String, T>) -> Result<Self, UnknownWeekday> {
+        let mut week = Self::new();
+        for (name, temperature) in map {
+            let index = DayIndex::from_name(name).ok_or_else(|| UnknownWeekday(name.clone()))?;
+            week.tempratures[index.get()] = Some(*temperature);
+        }
+        Ok(week)
+    }
+}
+
+impl<T: Copy + fmt::Display> WeekTemperatures<T> {
+    // Seven fields, Monday first, empty for unrecorded days: `20,,18,,,,-3`.
+    pub fn to_csv_row(&self) -> String {
+        self.tempratures
+            .iter()
+            .map(|temperature| temperature.map(|t| t.to_string()).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+impl<T: Copy + std::str::FromStr> WeekTemperatures<T> {
+    // The inverse of `to_csv_row`. Whitespace around fields is ignored.
+    pub fn from_csv_row(row: &str) -> Result<Self, CsvRowError> {
+        let fields: Vec<&str> = row.trim_end_matches(['\r', '\n']).split(',').collect();
+        if fields.len() != Weekday::COUNT {
+            return Err(CsvRowError::FieldCount(fields.len()));
+        }
+        let mut week = Self::new();
+        for (slot, field) in week.tempratures.iter_mut().zip(fields) {
+            let field = field.trim();
+            if !field.is_empty() {
+                let temperature = field
+                    .parse()
+                    .map_err(|_| CsvRowError::InvalidTemperature(field.to_string()))?;
+                *slot = Some(temperature);
+            }
+        }
+        Ok(week)
+    }
+}
+
+// A day that shows up more than once keeps its last reading.
+impl<T: Copy> FromIterator<(Weekday, T)> for WeekTemperatures<T> {
+    fn from_iter<I: IntoIterator<Item = (Weekday, T)>>(readings: I) -> Self {
+        let mut week = Self::new();
+        for (day, temperature) in readings {
+            week.set_temperature(day, temperature);
+        }
+        week
+    }
+}
+
+// One `Monday: 20` line per day, Monday first, with `-` for unrecorded days
+// and no trailing newline.
+impl<T: Copy + fmt::Display> fmt::Display for WeekTemperatures<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let days = WEEKDAY_NAMES.iter().zip(self.tempratures);
+        for (index, (name, temperature)) in days.enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            match temperature {
+                Some(t) => write!(f, "{}: {}", name, t)?,
+                None => write!(f, "{}: -", name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// Serialized as a map from weekday name to temperature, Monday first, with
+// unrecorded days left out: `{"Monday":20,"Wednesday":18}`.
+#[cfg(feature = "serde")]
+impl<T: Copy + serde::Serialize> serde::Serialize for WeekTemperatures<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Copy + serde::Deserialize<'de>> serde::Deserialize<'de> for WeekTemperatures<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let map = BTreeMap::<String, T>::deserialize(deserializer)?;
+        Self::from_named_map(&map).map_err(serde::de::Error::custom)
+    }
+}
+
+// Four consecutive weeks, first week first.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MonthTemperatures<T = i32> {
+    weeks: [WeekTemperatures<T>; 4],
+}
+
+impl<T: Copy> MonthTemperatures<T> {
+    pub fn new() -> Self {
+        MonthTemperatures {
+            weeks: std::array::from_fn(|_| WeekTemperatures::new()),
+        }
+    }
+
+    // Panics unless `n` is in `0..4`.
+    pub fn week(&self, n: usize) -> &WeekTemperatures<T> {
+        &self.weeks[n]
+    }
+
+    // Panics unless `n` is in `0..4`.
+    pub fn week_mut(&mut self, n: usize) -> &mut WeekTemperatures<T> {
+        &mut self.weeks[n]
+    }
+}
+
+impl<T: Copy> Default for MonthTemperatures<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + Into<f64>> MonthTemperatures<T> {
+    // Average over every recorded day in the month, if any.
+    pub fn average(&self) -> Option<f64> {
+        let recorded: Vec<f64> = self
+            .weeks
+            .iter()
+            .flat_map(|week| week.iter().map(|(_, t)| t.into()))
+            .collect();
+        if recorded.is_empty() {
+            return None;
+        }
+        Some(recorded.iter().sum::<f64>() / recorded.len() as f64)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,4 +583,498 @@ mod tests {
         );
         assert_eq!(week_temperatures.get_temperature(Weekday::Sunday), Some(55));
     }
+
+    #[test]
+    fn test_with_defaults() {
+        let mut week_temperatures = WeekTemperatures::with_defaults([1, 2, 3, 4, 5, 6, 7]);
+
+        assert_eq!(week_temperatures.get_temperature(Weekday::Monday), Some(1));
+        assert_eq!(week_temperatures.get_temperature(Weekday::Sunday), Some(7));
+
+        week_temperatures.set_temperature(Weekday::Monday, 20);
+        assert_eq!(week_temperatures.get_temperature(Weekday::Monday), Some(20));
+        assert_eq!(week_temperatures.get_temperature(Weekday::Tuesday), Some(2));
+    }
+
+    #[test]
+    fn test_named_map_round_trip() {
+        let mut week_temperatures = WeekTemperatures::new();
+        week_temperatures.set_temperature(Weekday::Monday, 20);
+        week_temperatures.set_temperature(Weekday::Sunday, -3);
+
+        let map = week_temperatures.to_named_map();
+        assert_eq!(
+            map,
+            BTreeMap::from([("Monday".to_string(), 20), ("Sunday".to_string(), -3)])
+        );
+
+        let round_tripped = WeekTemperatures::from_named_map(&map).unwrap();
+        assert_eq!(round_tripped.get_temperature(Weekday::Monday), Some(20));
+        assert_eq!(round_tripped.get_temperature(Weekday::Tuesday), None);
+        assert_eq!(round_tripped.get_temperature(Weekday::Sunday), Some(-3));
+        assert_eq!(round_tripped.to_named_map(), map);
+    }
+
+    #[test]
+    fn test_from_named_map_unknown_day() {
+        let map = BTreeMap::from([("Funday".to_string(), 20)]);
+        assert!(matches!(
+            WeekTemperatures::from_named_map(&map),
+            Err(UnknownWeekday(name)) if name == "Funday"
+        ));
+    }
+
+    #[test]
+    fn test_weekend_classification() {
+        assert!(Weekday::Saturday.is_weekend());
+        assert!(Weekday::Sunday.is_weekend());
+        assert!(!Weekday::Monday.is_weekend());
+        assert!(Weekday::Friday.is_weekday());
+        assert!(!Weekday::Sunday.is_weekday());
+    }
+
+    #[test]
+    fn test_weekend_average() {
+        let mut week_temperatures = WeekTemperatures::new();
+        week_temperatures.set_temperature(Weekday::Monday, 100);
+        assert_eq!(week_temperatures.weekend_average(), None);
+
+        week_temperatures.set_temperature(Weekday::Saturday, 20);
+        assert_eq!(week_temperatures.weekend_average(), Some(20.0));
+
+        week_temperatures.set_temperature(Weekday::Sunday, 25);
+        assert_eq!(week_temperatures.weekend_average(), Some(22.5));
+    }
+
+    #[test]
+    fn test_weekday_index_round_trips() {
+        for day in Weekday::all() {
+            assert_eq!(Weekday::all()[day.index()], day);
+        }
+        assert_eq!(Weekday::Monday.index(), 0);
+        assert_eq!(Weekday::Sunday.index(), 6);
+    }
+
+    #[test]
+    fn test_every_weekday_has_its_own_slot() {
+        let mut week = WeekTemperatures::new();
+        for (t, day) in (0..).zip(Weekday::all()) {
+            assert_eq!(week.set_temperature(day, t), None);
+        }
+        for (t, day) in (0..).zip(Weekday::all()) {
+            assert_eq!(week.get_temperature(day), Some(t));
+        }
+        assert_eq!(week.iter().count(), Weekday::COUNT);
+    }
+
+    #[test]
+    fn test_next_and_previous() {
+        assert_eq!(Weekday::Monday.next(), Weekday::Tuesday);
+        assert_eq!(Weekday::Sunday.next(), Weekday::Monday);
+        assert_eq!(Weekday::Monday.previous(), Weekday::Sunday);
+        assert_eq!(Weekday::Thursday.previous(), Weekday::Wednesday);
+
+        let mut day = Weekday::Monday;
+        for _ in 0..Weekday::COUNT {
+            assert_eq!(day.next().previous(), day);
+            day = day.next();
+        }
+        assert_eq!(day, Weekday::Monday);
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut week_temperatures = WeekTemperatures::new();
+        week_temperatures.set_temperature(Weekday::Friday, 22);
+        week_temperatures.set_temperature(Weekday::Tuesday, 17);
+
+        let recorded: Vec<_> = week_temperatures.iter().collect();
+        assert_eq!(
+            recorded,
+            vec![(Weekday::Tuesday, 17), (Weekday::Friday, 22)]
+        );
+    }
+
+    #[test]
+    fn test_hottest_and_coldest() {
+        let mut week_temperatures = WeekTemperatures::new();
+        assert_eq!(week_temperatures.hottest(), None);
+        assert_eq!(week_temperatures.coldest(), None);
+
+        week_temperatures.set_temperature(Weekday::Thursday, 12);
+        assert_eq!(week_temperatures.hottest(), Some((Weekday::Thursday, 12)));
+        assert_eq!(week_temperatures.coldest(), Some((Weekday::Thursday, 12)));
+
+        week_temperatures.set_temperature(Weekday::Sunday, 25);
+        week_temperatures.set_temperature(Weekday::Tuesday, 25);
+        week_temperatures.set_temperature(Weekday::Saturday, 3);
+        week_temperatures.set_temperature(Weekday::Wednesday, 3);
+        assert_eq!(week_temperatures.hottest(), Some((Weekday::Tuesday, 25)));
+        assert_eq!(week_temperatures.coldest(), Some((Weekday::Wednesday, 3)));
+    }
+
+    #[test]
+    fn test_get_temperature_fahrenheit() {
+        let mut week = WeekTemperatures::new();
+        assert_eq!(week.get_temperature_fahrenheit(Weekday::Monday), None);
+
+        week.set_temperature(Weekday::Monday, 0);
+        week.set_temperature(Weekday::Tuesday, 100);
+        week.set_temperature(Weekday::Wednesday, -40);
+        assert_eq!(week.get_temperature_fahrenheit(Weekday::Monday), Some(32.0));
+        assert_eq!(
+            week.get_temperature_fahrenheit(Weekday::Tuesday),
+            Some(212.0)
+        );
+        assert_eq!(
+            week.get_temperature_fahrenheit(Weekday::Wednesday),
+            Some(-40.0)
+        );
+        assert_eq!(week.get_temperature(Weekday::Tuesday), Some(100));
+    }
+
+    #[test]
+    fn test_set_temperature_returns_previous() {
+        let mut week = WeekTemperatures::new();
+        assert_eq!(week.set_temperature(Weekday::Monday, 10), None);
+        assert_eq!(week.set_temperature(Weekday::Monday, 14), Some(10));
+        assert_eq!(week.get_temperature(Weekday::Monday), Some(14));
+    }
+
+    #[test]
+    fn test_collect_readings() {
+        let readings = vec![
+            (Weekday::Monday, 10),
+            (Weekday::Friday, 20),
+            (Weekday::Monday, 12),
+        ];
+        let week: WeekTemperatures = readings.into_iter().collect();
+        assert_eq!(
+            week.iter().collect::<Vec<_>>(),
+            vec![(Weekday::Monday, 12), (Weekday::Friday, 20)]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut week = WeekTemperatures::new();
+        week.set_temperature(Weekday::Wednesday, 18);
+        week.set_temperature(Weekday::Monday, 20);
+
+        let json = serde_json::to_string(&week).unwrap();
+        assert_eq!(json, r#"{"Monday":20,"Wednesday":18}"#);
+        let back: WeekTemperatures = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            back.iter().collect::<Vec<_>>(),
+            week.iter().collect::<Vec<_>>()
+        );
+
+        assert!(serde_json::from_str::<WeekTemperatures>(r#"{"Funday":1}"#).is_err());
+        let day: Weekday = serde_json::from_str(r#""Friday""#).unwrap();
+        assert_eq!(day, Weekday::Friday);
+    }
+
+    #[test]
+    fn test_display() {
+        let mut week = WeekTemperatures::new();
+        week.set_temperature(Weekday::Wednesday, 18);
+        assert_eq!(
+            week.to_string(),
+            "Monday: -\n\
+             Tuesday: -\n\
+             Wednesday: 18\n\
+             Thursday: -\n\
+             Friday: -\n\
+             Saturday: -\n\
+             Sunday: -"
+        );
+    }
+
+    #[test]
+    fn test_float_readings() {
+        let mut week: WeekTemperatures<f32> = WeekTemperatures::new();
+        assert_eq!(week.set_temperature(Weekday::Monday, 21.5), None);
+        week.set_temperature(Weekday::Tuesday, 18.5);
+        assert_eq!(week.get_temperature(Weekday::Monday), Some(21.5));
+        assert_eq!(week.hottest(), Some((Weekday::Monday, 21.5)));
+        assert_eq!(week.average(), Some(20.0));
+        assert_eq!(week.to_csv_row(), "21.5,18.5,,,,,");
+    }
+
+    #[test]
+    fn test_std_dev() {
+        let mut week = WeekTemperaturesI32::new();
+        assert_eq!(week.std_dev(), None);
+
+        week.set_temperature(Weekday::Monday, 7);
+        assert_eq!(week.std_dev(), Some(0.0));
+
+        // Mean 15, every reading 5 away from it.
+        week.set_temperature(Weekday::Monday, 10);
+        week.set_temperature(Weekday::Tuesday, 20);
+        week.set_temperature(Weekday::Thursday, 10);
+        week.set_temperature(Weekday::Sunday, 20);
+        assert_eq!(week.std_dev(), Some(5.0));
+    }
+
+    #[test]
+    fn test_clear_temperature() {
+        let mut week = WeekTemperatures::new();
+        week.set_temperature(Weekday::Monday, 10);
+        week.set_temperature(Weekday::Tuesday, 12);
+
+        week.clear_temperature(Weekday::Monday);
+        assert_eq!(week.get_temperature(Weekday::Monday), None);
+        assert_eq!(week.get_temperature(Weekday::Tuesday), Some(12));
+
+        week.clear_all();
+        assert_eq!(week.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_average() {
+        let mut week_temperatures = WeekTemperatures::new();
+        assert_eq!(week_temperatures.average(), None);
+
+        week_temperatures.set_temperature(Weekday::Wednesday, 18);
+        assert_eq!(week_temperatures.average(), Some(18.0));
+
+        week_temperatures.set_temperature(Weekday::Monday, 10);
+        week_temperatures.set_temperature(Weekday::Sunday, 15);
+        assert_eq!(week_temperatures.average(), Some(43.0 / 3.0));
+    }
+
+    #[test]
+    fn test_days_above_and_below() {
+        let mut week_temperatures = WeekTemperatures::new();
+        week_temperatures.set_temperature(Weekday::Monday, 30);
+        week_temperatures.set_temperature(Weekday::Wednesday, 20);
+        week_temperatures.set_temperature(Weekday::Friday, 35);
+        week_temperatures.set_temperature(Weekday::Sunday, 10);
+
+        assert_eq!(
+            week_temperatures.days_above(25),
+            vec![Weekday::Monday, Weekday::Friday]
+        );
+        assert_eq!(
+            week_temperatures.days_below(25),
+            vec![Weekday::Wednesday, Weekday::Sunday]
+        );
+        assert!(week_temperatures.days_above(40).is_empty());
+        assert!(week_temperatures.days_below(0).is_empty());
+
+        // Exactly at the threshold counts as neither.
+        assert_eq!(week_temperatures.days_above(30), vec![Weekday::Friday]);
+        assert_eq!(week_temperatures.days_below(20), vec![Weekday::Sunday]);
+    }
+
+    #[test]
+    fn test_merge_with() {
+        let mut week_temperatures = WeekTemperatures::new();
+        week_temperatures.set_temperature(Weekday::Monday, 20);
+        week_temperatures.set_temperature(Weekday::Tuesday, 15);
+        let mut other = WeekTemperatures::new();
+        other.set_temperature(Weekday::Monday, 18);
+        other.set_temperature(Weekday::Tuesday, 22);
+        other.set_temperature(Weekday::Friday, 30);
+
+        week_temperatures.merge_with(&other, |a, b| *a.max(b));
+        assert_eq!(week_temperatures.get_temperature(Weekday::Monday), Some(20));
+        assert_eq!(
+            week_temperatures.get_temperature(Weekday::Tuesday),
+            Some(22)
+        );
+        assert_eq!(week_temperatures.get_temperature(Weekday::Wednesday), None);
+        assert_eq!(week_temperatures.get_temperature(Weekday::Friday), Some(30));
+    }
+
+    #[test]
+    fn test_set_with_sentinel() {
+        let mut week_temperatures = WeekTemperatures::new();
+        week_temperatures.set_with_sentinel(Weekday::Monday, 21, -9999);
+        assert_eq!(week_temperatures.get_temperature(Weekday::Monday), Some(21));
+
+        week_temperatures.set_with_sentinel(Weekday::Monday, -9999, -9999);
+        assert_eq!(week_temperatures.get_temperature(Weekday::Monday), None);
+        week_temperatures.set_with_sentinel(Weekday::Tuesday, -9999, -9999);
+        assert_eq!(week_temperatures.get_temperature(Weekday::Tuesday), None);
+    }
+
+    #[test]
+    fn test_csv_row_round_trip() {
+        let mut week_temperatures = WeekTemperatures::new();
+        week_temperatures.set_temperature(Weekday::Monday, 20);
+        week_temperatures.set_temperature(Weekday::Wednesday, 18);
+        week_temperatures.set_temperature(Weekday::Sunday, -3);
+
+        let row = week_temperatures.to_csv_row();
+        assert_eq!(row, "20,,18,,,,-3");
+        let round_tripped = WeekTemperatures::from_csv_row(&row).unwrap();
+        assert_eq!(
+            round_tripped.to_named_map(),
+            week_temperatures.to_named_map()
+        );
+        assert_eq!(WeekTemperaturesI32::new().to_csv_row(), ",,,,,,");
+    }
+
+    #[test]
+    fn test_from_csv_row_errors() {
+        assert_eq!(
+            WeekTemperaturesI32::from_csv_row("1,2,3").err(),
+            Some(CsvRowError::FieldCount(3))
+        );
+        assert_eq!(
+            WeekTemperaturesI32::from_csv_row("1,2,warm,4,5,6,7").err(),
+            Some(CsvRowError::InvalidTemperature("warm".to_string()))
+        );
+        let week = WeekTemperatures::from_csv_row(" 1, ,3,,,,7\n").unwrap();
+        assert_eq!(week.get_temperature(Weekday::Monday), Some(1));
+        assert_eq!(week.get_temperature(Weekday::Tuesday), None);
+    }
+
+    #[test]
+    fn test_day_index() {
+        assert_eq!(DayIndex::new(0).unwrap().get(), 0);
+        assert_eq!(DayIndex::new(6).unwrap().get(), 6);
+        assert_eq!(DayIndex::new(7), Err(DayIndexOutOfRange(7)));
+
+        assert_eq!(DayIndex::from(Weekday::Monday), DayIndex::new(0).unwrap());
+        assert_eq!(DayIndex::from(Weekday::Sunday), DayIndex::new(6).unwrap());
+        assert_eq!(Weekday::from(DayIndex::new(2).unwrap()), Weekday::Wednesday);
+        assert_eq!(DayIndex::from_name("Friday"), DayIndex::new(4).ok());
+        assert_eq!(DayIndex::from_name("Funday"), None);
+    }
+
+    #[test]
+    fn test_month_average() {
+        let mut month = MonthTemperatures::new();
+        assert_eq!(month.average(), None);
+
+        month.week_mut(0).set_temperature(Weekday::Monday, 10);
+        month.week_mut(0).set_temperature(Weekday::Sunday, 20);
+        month.week_mut(2).set_temperature(Weekday::Wednesday, 30);
+        assert_eq!(month.average(), Some(20.0));
+        assert_eq!(month.week(0).get_temperature(Weekday::Sunday), Some(20));
+        assert_eq!(month.week(1).get_temperature(Weekday::Sunday), None);
+
+        month.week_mut(3).set_temperature(Weekday::Friday, -20);
+        assert_eq!(month.average(), Some(10.0));
+    }
+
+    #[test]
+    fn test_month_default_and_clone() {
+        let mut month = MonthTemperatures::default();
+        assert_eq!(month, MonthTemperatures::new());
+
+        let copy = month.clone();
+        month.week_mut(1).set_temperature(Weekday::Tuesday, 5);
+        assert_ne!(month, copy);
+    }
+
+    #[test]
+    fn test_recorded_count_and_completeness() {
+        let mut week = WeekTemperaturesI32::new();
+        assert_eq!(week.recorded_count(), 0);
+        assert!(!week.is_complete());
+
+        week.set_temperature(Weekday::Monday, 10);
+        week.set_temperature(Weekday::Thursday, 15);
+        assert_eq!(week.recorded_count(), 2);
+        assert!(!week.is_complete());
+
+        let week = WeekTemperatures::with_defaults([1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(week.recorded_count(), 7);
+        assert!(week.is_complete());
+    }
+
+    #[test]
+    fn test_clones_compare_equal_until_changed() {
+        let mut week = WeekTemperaturesI32::new();
+        week.set_temperature(Weekday::Monday, 10);
+        week.set_temperature(Weekday::Sunday, 25);
+
+        let mut snapshot = week.clone();
+        assert_eq!(snapshot, week);
+
+        snapshot.set_temperature(Weekday::Sunday, 26);
+        assert_ne!(snapshot, week);
+    }
+
+    #[test]
+    fn test_interpolate_missing_days() {
+        let mut week = WeekTemperaturesI32::new();
+        week.set_temperature(Weekday::Monday, 10);
+        week.set_temperature(Weekday::Friday, 50);
+        week.interpolate_missing();
+        let filled: Vec<i32> = week.iter().map(|(_, t)| t).collect();
+        assert_eq!(filled, vec![10, 20, 30, 40, 50, 50, 50]);
+
+        let mut week = WeekTemperatures::<f64>::new();
+        week.set_temperature(Weekday::Thursday, 4.0);
+        week.set_temperature(Weekday::Sunday, 5.5);
+        week.interpolate_missing();
+        assert_eq!(week.get_temperature(Weekday::Monday), Some(4.0));
+        assert_eq!(week.get_temperature(Weekday::Friday), Some(4.5));
+        assert_eq!(week.get_temperature(Weekday::Saturday), Some(5.0));
+
+        let mut week = WeekTemperatures::<u16>::new();
+        week.set_temperature(Weekday::Tuesday, 50);
+        week.set_temperature(Weekday::Saturday, 10);
+        week.interpolate_missing();
+        let filled: Vec<u16> = week.iter().map(|(_, t)| t).collect();
+        assert_eq!(filled, vec![50, 50, 40, 30, 20, 10, 10]);
+
+        // Stretches close to `u8::MAX` don't overflow on the way.
+        let mut week = WeekTemperatures::<u8>::new();
+        week.set_temperature(Weekday::Monday, 0);
+        week.set_temperature(Weekday::Saturday, 250);
+        week.interpolate_missing();
+        let filled: Vec<u8> = week.iter().map(|(_, t)| t).collect();
+        assert_eq!(filled, vec![0, 50, 100, 150, 200, 250, 250]);
+
+        let mut week = WeekTemperatures::<u8>::new();
+        week.set_temperature(Weekday::Monday, 255);
+        week.set_temperature(Weekday::Thursday, 1);
+        week.interpolate_missing();
+        let filled: Vec<u8> = week.iter().map(|(_, t)| t).collect();
+        assert_eq!(filled, vec![255, 171, 86, 1, 1, 1, 1]);
+
+        let mut week = WeekTemperaturesI32::new();
+        week.interpolate_missing();
+        assert_eq!(week.recorded_count(), 0);
+    }
+
+    #[test]
+    fn test_days_in_range_is_inclusive() {
+        let mut week = WeekTemperaturesI32::new();
+        week.set_temperature(Weekday::Monday, 12);
+        week.set_temperature(Weekday::Tuesday, 18);
+        week.set_temperature(Weekday::Wednesday, 30);
+        week.set_temperature(Weekday::Thursday, 24);
+        week.set_temperature(Weekday::Saturday, 17);
+        week.set_temperature(Weekday::Sunday, 25);
+
+        assert_eq!(
+            week.days_in_range(18, 24),
+            vec![Weekday::Tuesday, Weekday::Thursday]
+        );
+        assert_eq!(week.days_in_range(31, 40), vec![]);
+        assert_eq!(week.days_in_range(24, 18), vec![]);
+    }
+
+    #[test]
+    fn test_weekday_as_map_key() {
+        let reversed = Weekday::all().into_iter().rev();
+        let totals: BTreeMap<Weekday, i32> = reversed.clone().map(|day| (day, 0)).collect();
+        assert_eq!(totals.keys().copied().collect::<Vec<_>>(), Weekday::all());
+        assert!(Weekday::Monday < Weekday::Sunday);
+
+        let mut counts = std::collections::HashMap::new();
+        for day in reversed.chain([Weekday::Friday]) {
+            *counts.entry(day).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), Weekday::COUNT);
+        assert_eq!(counts[&Weekday::Friday], 2);
+    }
 }
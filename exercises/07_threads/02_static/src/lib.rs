@@ -1,15 +1,41 @@
-// TODO: Given a static slice of integers, split the slice into two halves and
+// TODO: Given a slice of integers, split the slice into two halves and
 //  sum each half in a separate thread.
 //  Do not allocate any additional memory!
 use std::thread;
 
-pub fn sum(slice: &'static [i32]) -> i32 {
+// Scoped threads can borrow `slice` because they're joined before `sum`
+// returns, so any slice will do, not just a `'static` one.
+pub fn sum<T>(slice: &[T]) -> T
+where
+    T: Send + Sync + Copy + std::iter::Sum<T>,
+{
     let (s1, s2) = slice.split_at(slice.len()/2);
-    let t1 = thread::spawn(||s1.iter().sum());
-    let t2 = thread::spawn(||s2.iter().sum());
-    let s1s:i32=t1.join().unwrap();
-    let s2s:i32=t2.join().unwrap();
-    s1s+s2s
+    thread::scope(|scope| {
+        let t1 = scope.spawn(||s1.iter().copied().sum());
+        let t2 = scope.spawn(||s2.iter().copied().sum());
+        let s1s:T=t1.join().unwrap();
+        let s2s:T=t2.join().unwrap();
+        [s1s, s2s].into_iter().sum()
+    })
+}
+
+// Like `sum`, but over `n` contiguous chunks whose lengths differ by at most
+// one, each in its own thread. `n` is clamped to between 1 and one thread
+// per element.
+pub fn sum_with_threads(slice: &[i32], n: usize) -> i32 {
+    let threads = n.clamp(1, slice.len().max(1));
+    let (base, extra) = (slice.len() / threads, slice.len() % threads);
+    let mut rest = slice;
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|i| {
+                let (chunk, tail) = rest.split_at(base + usize::from(i < extra));
+                rest = tail;
+                scope.spawn(move || chunk.iter().sum::<i32>())
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).sum()
+    })
 }
 
 #[cfg(test)]
@@ -18,31 +44,80 @@ mod tests {
 
     #[test]
     fn empty() {
-        static ARRAY: [i32; 0] = [];
-        assert_eq!(sum(&ARRAY), 0);
+        let array: [i32; 0] = [];
+        assert_eq!(sum(&array), 0);
     }
 
     #[test]
     fn one() {
-        static ARRAY: [i32; 1] = [1];
-        assert_eq!(sum(&ARRAY), 1);
+        let array = [1];
+        assert_eq!(sum(&array), 1);
     }
 
     #[test]
     fn five() {
-        static ARRAY: [i32; 5] = [1, 2, 3, 4, 5];
-        assert_eq!(sum(&ARRAY), 15);
+        let array = [1, 2, 3, 4, 5];
+        assert_eq!(sum(&array), 15);
     }
 
     #[test]
     fn nine() {
-        static ARRAY: [i32; 9] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
-        assert_eq!(sum(&ARRAY), 45);
+        let array = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        assert_eq!(sum(&array), 45);
     }
 
     #[test]
     fn ten() {
+        let array = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        assert_eq!(sum(&array), 55);
+    }
+
+    #[test]
+    fn floats() {
+        let array = [0.5, 1.25, 2.0, 4.25];
+        assert_eq!(sum(&array), 8.0);
+    }
+
+    #[test]
+    fn wide() {
+        let array = [i64::MAX / 2, i64::MAX / 2, 1];
+        assert_eq!(sum(&array), i64::MAX);
+    }
+
+    #[test]
+    fn heap() {
+        let numbers: Vec<i32> = (1..=100).collect();
+        assert_eq!(sum(&numbers), 5050);
+    }
+
+    #[test]
+    fn one_thread() {
         static ARRAY: [i32; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
-        assert_eq!(sum(&ARRAY), 55);
+        assert_eq!(sum_with_threads(&ARRAY, 1), 55);
+        assert_eq!(sum_with_threads(&ARRAY, 0), 55);
+    }
+
+    #[test]
+    fn three_threads() {
+        static ARRAY: [i32; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+        assert_eq!(sum_with_threads(&ARRAY, 3), 55);
+    }
+
+    #[test]
+    fn more_threads_than_elements() {
+        static ARRAY: [i32; 5] = [1, 2, 3, 4, 5];
+        assert_eq!(sum_with_threads(&ARRAY, 64), 15);
+    }
+
+    #[test]
+    fn threads_on_heap() {
+        let numbers: Vec<i32> = (1..=100).collect();
+        assert_eq!(sum_with_threads(&numbers, 7), 5050);
+    }
+
+    #[test]
+    fn threads_on_empty() {
+        static ARRAY: [i32; 0] = [];
+        assert_eq!(sum_with_threads(&ARRAY, 4), 0);
     }
 }
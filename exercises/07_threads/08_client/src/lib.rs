@@ -1,67 +1,268 @@
+use crate::channel::{Receiver, Sender};
 use crate::data::{Ticket, TicketDraft};
 use crate::store::{TicketId, TicketStore};
-use std::sync::mpsc::{Receiver, Sender};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
 
 pub mod data;
 pub mod store;
 
+// Both backends expose the same `send`/`recv` surface, so the rest of the
+// crate doesn't need to know which one is in use.
+#[cfg(not(feature = "crossbeam"))]
+mod channel {
+    pub use std::sync::mpsc::{channel as unbounded, Receiver, Sender};
+}
+#[cfg(feature = "crossbeam")]
+mod channel {
+    pub use crossbeam_channel::{unbounded, Receiver, Sender};
+}
+
 #[derive(Clone)]
 // TODO: flesh out the client implementation.
 pub struct TicketStoreClient {
     cmd_sender: Sender<Command>,
-    insert_response_sender: Sender<TicketId>,
-    insert_response_receiver: Arc<Receiver<TicketId>>,
-    get_response_sender: Sender<Option<Ticket>>,
-    get_response_receiver: Arc<Receiver<Option<Ticket>>>,
+    next_request_id: Arc<AtomicU64>,
+    replies: Arc<Replies>,
+    // Held for its `Drop`, and to tell whether the server is still running.
+    server: Arc<ServerHandle>,
 }
 
 impl TicketStoreClient {
-    // Feel free to panic on all errors, for simplicity.
-    pub fn insert(&self, draft: TicketDraft) -> TicketId {
-        self.cmd_sender.send(Command::Insert {
-            draft: draft,
-            response_channel: self.insert_response_sender.clone(),
-        }).unwrap();
-        self.insert_response_receiver.recv().unwrap()
+    pub fn insert(&self, draft: TicketDraft) -> Result<TicketId, ServerGone> {
+        self.call(|response_channel| Command::Insert {
+            draft,
+            response_channel,
+        })
     }
 
-    pub fn get(&self, id: TicketId) -> Option<Ticket> {
-        self.cmd_sender.send(Command::Get {
+    pub fn get(&self, id: TicketId) -> Result<Option<Ticket>, ServerGone> {
+        self.call(|response_channel| Command::Get {
             id,
-            response_channel: self.get_response_sender.clone(),
-        }).unwrap();
-        self.get_response_receiver.recv().unwrap()
+            response_channel,
+        })
+    }
+
+    // The command fixes the type of its reply, so `insert` can't end up
+    // waiting for a ticket.
+    fn call<T: Reply>(
+        &self,
+        command: impl FnOnce(ResponseChannel<T>) -> Command,
+    ) -> Result<T, ServerGone> {
+        self.ensure_server_alive()?;
+        let request_id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        self.cmd_sender
+            .send(command(ResponseChannel::new(request_id)))
+            .map_err(|_| ServerGone)?;
+        self.replies.wait_for(request_id)
+    }
+
+    // The server thread only finishes if it panicked or was told to shut
+    // down, which only happens once the last client is gone, so a finished
+    // thread means a dead server.
+    fn ensure_server_alive(&self) -> Result<(), ServerGone> {
+        if self.server.is_finished() {
+            Err(ServerGone)
+        } else {
+            Ok(())
+        }
     }
 }
 
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("The server thread is no longer running")]
+pub struct ServerGone;
+
 pub fn launch() -> TicketStoreClient {
-    let (sender, receiver) = std::sync::mpsc::channel();
-    std::thread::spawn(move || server(receiver));
-    let (insert_response_sender, insert_response_receiver) = std::sync::mpsc::channel();
-    let (get_response_sender, get_response_receiver) = std::sync::mpsc::channel();
+    launch_with_stop_flag(Arc::default())
+}
+
+// `stopped` is set once the server thread is done.
+fn launch_with_stop_flag(stopped: Arc<AtomicBool>) -> TicketStoreClient {
+    let (sender, receiver) = channel::unbounded();
+    let (response_sender, response_receiver) = channel::unbounded();
+    let thread = std::thread::spawn({
+        let stopped = stopped.clone();
+        move || {
+            server(receiver, response_sender);
+            stopped.store(true, Ordering::Release);
+        }
+    });
     TicketStoreClient {
-        cmd_sender: sender,
-        insert_response_sender,
-        insert_response_receiver: Arc::new(insert_response_receiver),
-        get_response_sender: get_response_sender,
-        get_response_receiver: Arc::new(get_response_receiver),
+        cmd_sender: sender.clone(),
+        next_request_id: Arc::new(AtomicU64::new(0)),
+        replies: Arc::new(Replies::new(response_receiver)),
+        server: Arc::new(ServerHandle {
+            cmd_sender: sender,
+            thread: Some(thread),
+        }),
+    }
+}
+
+// Shared by every clone of a client, so it's dropped together with the last
+// one: that's when the server is told to stop and waited for.
+struct ServerHandle {
+    cmd_sender: Sender<Command>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_some_and(JoinHandle::is_finished)
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        // The server may already be gone, e.g. if it panicked; either way
+        // there's nothing left to stop.
+        let _ = self.cmd_sender.send(Command::Shutdown);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
     }
 }
 
+// Tags a command so its reply can be told apart from the others in flight.
+type RequestId = u64;
+
 // No longer public! This becomes an internal detail of the library now.
 enum Command {
     Insert {
         draft: TicketDraft,
-        response_channel: Sender<TicketId>,
+        response_channel: InsertResponse,
     },
     Get {
         id: TicketId,
-        response_channel: Sender<Option<Ticket>>,
+        response_channel: GetResponse,
     },
+    Shutdown,
+}
+
+// Every reply goes over the one shared channel, but each command still
+// carries its own response type, so a refactor can't answer an insert with
+// a ticket.
+struct ResponseChannel<T> {
+    request_id: RequestId,
+    _reply: PhantomData<fn(T)>,
 }
 
-fn server(receiver: Receiver<Command>) {
+type InsertResponse = ResponseChannel<TicketId>;
+type GetResponse = ResponseChannel<Option<Ticket>>;
+
+impl<T: Reply> ResponseChannel<T> {
+    fn new(request_id: RequestId) -> Self {
+        Self {
+            request_id,
+            _reply: PhantomData,
+        }
+    }
+
+    // Sending only fails if the client is gone, and then nobody is waiting.
+    fn send(self, responses: &Sender<Response>, reply: T) {
+        let _ = responses.send(reply.into_response(self.request_id));
+    }
+}
+
+// The server echoes the request id of the command it's answering.
+enum Response {
+    Inserted(RequestId, TicketId),
+    Got(RequestId, Option<Ticket>),
+}
+
+// Ties each reply type to its `Response` variant and to the table its
+// waiters look in.
+trait Reply: Sized {
+    fn into_response(self, request_id: RequestId) -> Response;
+    fn parked(state: &mut ReplyState) -> &mut HashMap<RequestId, Self>;
+}
+
+impl Reply for TicketId {
+    fn into_response(self, request_id: RequestId) -> Response {
+        Response::Inserted(request_id, self)
+    }
+
+    fn parked(state: &mut ReplyState) -> &mut HashMap<RequestId, Self> {
+        &mut state.inserted
+    }
+}
+
+impl Reply for Option<Ticket> {
+    fn into_response(self, request_id: RequestId) -> Response {
+        Response::Got(request_id, self)
+    }
+
+    fn parked(state: &mut ReplyState) -> &mut HashMap<RequestId, Self> {
+        &mut state.got
+    }
+}
+
+// Replies for every clone of a client share one channel. A caller that finds
+// nobody else reading it reads a reply, parks it for whoever it belongs to
+// and wakes everyone up; the others wait until their reply has been parked.
+// So any number of calls can be in flight at once.
+struct Replies {
+    receiver: Mutex<Receiver<Response>>,
+    state: Mutex<ReplyState>,
+    arrived: Condvar,
+}
+
+#[derive(Default)]
+struct ReplyState {
+    inserted: HashMap<RequestId, TicketId>,
+    got: HashMap<RequestId, Option<Ticket>>,
+    reading: bool,
+    // Set once the server has hung up: no more replies are coming.
+    disconnected: bool,
+}
+
+impl Replies {
+    fn new(receiver: Receiver<Response>) -> Self {
+        Self {
+            receiver: Mutex::new(receiver),
+            state: Mutex::default(),
+            arrived: Condvar::new(),
+        }
+    }
+
+    // Fails if the server hangs up before sending the reply, which wakes up
+    // every other waiter so they fail too instead of waiting forever.
+    fn wait_for<T: Reply>(&self, request_id: RequestId) -> Result<T, ServerGone> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(reply) = T::parked(&mut state).remove(&request_id) {
+                return Ok(reply);
+            }
+            if state.disconnected {
+                return Err(ServerGone);
+            }
+            if state.reading {
+                state = self.arrived.wait(state).unwrap();
+                continue;
+            }
+            state.reading = true;
+            drop(state);
+            let response = self.receiver.lock().unwrap().recv();
+            state = self.state.lock().unwrap();
+            state.reading = false;
+            match response {
+                Ok(Response::Inserted(request_id, id)) => {
+                    state.inserted.insert(request_id, id);
+                }
+                Ok(Response::Got(request_id, ticket)) => {
+                    state.got.insert(request_id, ticket);
+                }
+                Err(_) => state.disconnected = true,
+            }
+            self.arrived.notify_all();
+        }
+    }
+}
+
+fn server(receiver: Receiver<Command>, responses: Sender<Response>) {
     let mut store = TicketStore::new();
     loop {
         match receiver.recv() {
@@ -70,15 +271,16 @@ fn server(receiver: Receiver<Command>) {
                 response_channel,
             }) => {
                 let id = store.add_ticket(draft);
-                let _ = response_channel.send(id);
+                response_channel.send(&responses, id);
             }
             Ok(Command::Get {
                 id,
                 response_channel,
             }) => {
                 let ticket = store.get(id);
-                let _ = response_channel.send(ticket.cloned());
+                response_channel.send(&responses, ticket.cloned());
             }
+            Ok(Command::Shutdown) => break,
             Err(_) => {
                 // There are no more senders, so we can safely break
                 // and shut down the server.
@@ -87,3 +289,149 @@ fn server(receiver: Receiver<Command>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use ticket_fields::test_helpers::{ticket_description, ticket_title};
+
+    #[test]
+    fn server_echoes_request_ids() {
+        let (command_sender, command_receiver) = channel::unbounded();
+        let (response_sender, response_receiver) = channel::unbounded();
+        std::thread::spawn(move || server(command_receiver, response_sender));
+
+        command_sender
+            .send(Command::Insert {
+                draft: TicketDraft {
+                    title: ticket_title(),
+                    description: ticket_description(),
+                },
+                response_channel: ResponseChannel::new(7),
+            })
+            .unwrap();
+        let Ok(Response::Inserted(7, id)) = response_receiver.recv() else {
+            panic!("expected an insert reply to request 7");
+        };
+
+        command_sender
+            .send(Command::Get {
+                id,
+                response_channel: ResponseChannel::new(3),
+            })
+            .unwrap();
+        let response = response_receiver.recv().unwrap();
+        assert!(matches!(response, Response::Got(3, Some(ticket)) if ticket.id == id));
+    }
+
+    #[test]
+    fn responses_go_to_their_own_waiters() {
+        let (response_sender, response_receiver) = channel::unbounded();
+        let replies = Replies::new(response_receiver);
+        let id = TicketStore::new().add_ticket(TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        });
+
+        // Swapping the reply types in these two would not compile.
+        let get: GetResponse = ResponseChannel::new(1);
+        get.send(&response_sender, None);
+        let insert: InsertResponse = ResponseChannel::new(0);
+        insert.send(&response_sender, id);
+
+        // Reading the insert reply parks the get reply on the way.
+        assert_eq!(replies.wait_for::<TicketId>(0), Ok(id));
+        assert_eq!(replies.wait_for::<Option<Ticket>>(1), Ok(None));
+    }
+
+    #[test]
+    fn dropping_the_last_client_stops_the_server() {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let client = launch_with_stop_flag(stopped.clone());
+        let clone = client.clone();
+
+        drop(client);
+        assert!(!stopped.load(Ordering::Acquire));
+        let id = clone
+            .insert(TicketDraft {
+                title: ticket_title(),
+                description: ticket_description(),
+            })
+            .unwrap();
+        assert!(clone.get(id).unwrap().is_some());
+
+        // Dropping the last client waits for the server thread to exit.
+        drop(clone);
+        assert!(stopped.load(Ordering::Acquire));
+    }
+
+    #[test]
+    fn waiters_fail_when_the_server_hangs_up() {
+        let (sender, receiver) = channel::unbounded();
+        let (response_sender, response_receiver) = channel::unbounded::<Response>();
+        // Takes a command from each caller, then dies without answering.
+        let thread = std::thread::spawn(move || {
+            let _responses = response_sender;
+            for _ in 0..4 {
+                let _ = receiver.recv();
+            }
+        });
+        let client = TicketStoreClient {
+            cmd_sender: sender.clone(),
+            next_request_id: Arc::new(AtomicU64::new(0)),
+            replies: Arc::new(Replies::new(response_receiver)),
+            server: Arc::new(ServerHandle {
+                cmd_sender: sender,
+                thread: Some(thread),
+            }),
+        };
+
+        let id = TicketStore::new().add_ticket(TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        });
+        let callers: Vec<_> = (0..4)
+            .map(|_| {
+                let client = client.clone();
+                std::thread::spawn(move || client.get(id))
+            })
+            .collect();
+        for caller in callers {
+            assert_eq!(caller.join().unwrap(), Err(ServerGone));
+        }
+    }
+
+    #[test]
+    fn server_gone_after_panic() {
+        let (sender, receiver) = channel::unbounded::<Command>();
+        let (response_sender, response_receiver) = channel::unbounded::<Response>();
+        // Leaks both channel ends so that only the finished thread gives the
+        // server away: sends still succeed and replies would never arrive.
+        let thread = std::thread::spawn(move || {
+            std::mem::forget(receiver);
+            std::mem::forget(response_sender);
+            panic!("The server blew up");
+        });
+        while !thread.is_finished() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let client = TicketStoreClient {
+            cmd_sender: sender.clone(),
+            next_request_id: Arc::new(AtomicU64::new(0)),
+            replies: Arc::new(Replies::new(response_receiver)),
+            server: Arc::new(ServerHandle {
+                cmd_sender: sender,
+                thread: Some(thread),
+            }),
+        };
+
+        let draft = TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        };
+        let id = TicketStore::new().add_ticket(draft.clone());
+        assert_eq!(client.insert(draft), Err(ServerGone));
+        assert_eq!(client.get(id), Err(ServerGone));
+    }
+}
@@ -1,6 +1,9 @@
 use client::data::{Status, TicketDraft};
 use client::launch;
+use client::store::TicketId;
+use std::collections::BTreeSet;
 use ticket_fields::test_helpers::{ticket_description, ticket_title};
+use ticket_fields::TicketTitle;
 
 #[test]
 fn insert_works() {
@@ -10,12 +13,41 @@ fn insert_works() {
         title: ticket_title(),
         description: ticket_description(),
     };
-    let ticket_id = client.insert(draft.clone());
+    let ticket_id = client.insert(draft.clone()).unwrap();
 
     let client2 = client.clone();
-    let ticket = client2.get(ticket_id).unwrap();
+    let ticket = client2.get(ticket_id).unwrap().unwrap();
     assert_eq!(ticket_id, ticket.id);
     assert_eq!(ticket.status, Status::ToDo);
     assert_eq!(ticket.title, draft.title);
     assert_eq!(ticket.description, draft.description);
 }
+
+#[test]
+fn concurrent_calls_get_their_own_replies() {
+    let client = launch();
+    let workers: Vec<_> = (0..8)
+        .map(|worker| {
+            let client = client.clone();
+            std::thread::spawn(move || {
+                (0..50)
+                    .map(|n| {
+                        let draft = TicketDraft {
+                            title: TicketTitle::try_from(format!("Ticket {worker}-{n}")).unwrap(),
+                            description: ticket_description(),
+                        };
+                        let id = client.insert(draft.clone()).unwrap();
+                        assert_eq!(client.get(id).unwrap().unwrap().title, draft.title);
+                        id
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+
+    let ids: BTreeSet<TicketId> = workers
+        .into_iter()
+        .flat_map(|worker| worker.join().unwrap())
+        .collect();
+    assert_eq!(ids.len(), 8 * 50);
+}
@@ -1,76 +1,347 @@
-// TODO: Convert the implementation to use bounded channels.
 use crate::data::{Ticket, TicketDraft};
 use crate::store::{TicketId, TicketStore};
-use std::sync::mpsc::{self, Receiver, SendError, Sender};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
 
 pub mod data;
 pub mod store;
 
 #[derive(Clone)]
 pub struct TicketStoreClient {
-    sender: Sender<Command>,
+    sender: SyncSender<Command>,
+    capacity: usize,
+    policy: BackpressurePolicy,
+    server: Arc<JoinHandle<()>>,
 }
 
 impl TicketStoreClient {
-    pub fn insert(&self, draft: TicketDraft) -> Result<TicketId, SendError<Command>> {
-        let (tx, rx) = mpsc::channel();
-        self.sender.send(Command::Insert {
+    // What happens when the queue is full depends on the policy the server
+    // was launched with, see `BackpressurePolicy`.
+    pub fn insert(&self, draft: TicketDraft) -> Result<Insertion, InsertError> {
+        self.ensure_server_alive()?;
+        let (tx, rx) = mpsc::sync_channel(1);
+        let command = Command::Insert {
             draft,
             response_channel: tx,
-        })?;
-        Ok(rx.recv().unwrap())
+        };
+        match self.policy {
+            BackpressurePolicy::Block => self.sender.send(command).map_err(|_| ServerGone)?,
+            BackpressurePolicy::DropNewest | BackpressurePolicy::Error => {
+                match self.sender.try_send(command) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(_)) if self.policy == BackpressurePolicy::Error => {
+                        return Err(InsertError::Full)
+                    }
+                    Err(TrySendError::Full(_)) => return Ok(Insertion::Dropped),
+                    Err(TrySendError::Disconnected(_)) => return Err(ServerGone.into()),
+                }
+            }
+        }
+        let id = rx.recv().map_err(|_| ServerGone)?;
+        Ok(Insertion::Inserted(id))
     }
 
-    pub fn get(&self, id: TicketId) -> Result<Option<Ticket>, SendError<Command>> {
-        let (tx, rx) = mpsc::channel();
-        self.sender.send(Command::Get {
-            id,
-            response_channel: tx,
-        })?;
-        Ok(rx.recv().unwrap())
+    pub fn get(&self, id: TicketId) -> Result<Option<Ticket>, ServerGone> {
+        self.ensure_server_alive()?;
+        let (tx, rx) = mpsc::sync_channel(1);
+        self.sender
+            .send(Command::Get {
+                id,
+                response_channel: tx,
+            })
+            .map_err(|_| ServerGone)?;
+        rx.recv().map_err(|_| ServerGone)
+    }
+
+    // Stops the server once it has answered every command queued before this
+    // one, returning when it has. Later requests from any client fail with
+    // `ServerGone`.
+    pub fn drain_and_stop(&self) -> Result<(), ServerGone> {
+        self.ensure_server_alive()?;
+        let (tx, rx) = mpsc::sync_channel(1);
+        self.sender
+            .send(Command::DrainAndStop {
+                response_channel: tx,
+            })
+            .map_err(|_| ServerGone)?;
+        rx.recv().map_err(|_| ServerGone)
+    }
+
+    // How many commands can be queued before the backpressure policy kicks in.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn policy(&self) -> BackpressurePolicy {
+        self.policy
+    }
+
+    // The server thread only finishes if it panicked or all senders are gone,
+    // and we're still holding one, so a finished thread means a dead server.
+    fn ensure_server_alive(&self) -> Result<(), ServerGone> {
+        if self.server.is_finished() {
+            Err(ServerGone)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("The server thread is no longer running")]
+pub struct ServerGone;
+
+// What `insert` does when the server's queue is full.
+// `get` and `drain_and_stop` always wait for room.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BackpressurePolicy {
+    // Wait until the server makes room.
+    #[default]
+    Block,
+    // Give up on the insert and return `Insertion::Dropped`.
+    DropNewest,
+    // Give up on the insert and return `InsertError::Full`.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Insertion {
+    Inserted(TicketId),
+    // The queue was full and the ticket was never sent to the server.
+    Dropped,
+}
+
+impl Insertion {
+    pub fn id(self) -> Option<TicketId> {
+        match self {
+            Insertion::Inserted(id) => Some(id),
+            Insertion::Dropped => None,
+        }
     }
 }
 
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum InsertError {
+    #[error(transparent)]
+    ServerGone(#[from] ServerGone),
+    #[error("The server queue is full")]
+    Full,
+}
+
 pub fn launch(capacity: usize) -> TicketStoreClient {
-    let (sender, receiver) = mpsc::channel();
-    std::thread::spawn(move || server(receiver));
-    TicketStoreClient { sender }
+    launch_with_policy(capacity, BackpressurePolicy::default())
+}
+
+pub fn launch_with_policy(capacity: usize, policy: BackpressurePolicy) -> TicketStoreClient {
+    let (sender, receiver) = mpsc::sync_channel(capacity);
+    let server = std::thread::spawn(move || server(receiver));
+    TicketStoreClient {
+        sender,
+        capacity,
+        policy,
+        server: Arc::new(server),
+    }
 }
 
 pub enum Command {
     Insert {
         draft: TicketDraft,
-        response_channel: Sender<TicketId>,
+        response_channel: SyncSender<TicketId>,
     },
     Get {
         id: TicketId,
-        response_channel: Sender<Option<Ticket>>,
+        response_channel: SyncSender<Option<Ticket>>,
+    },
+    DrainAndStop {
+        response_channel: SyncSender<()>,
     },
 }
 
 pub fn server(receiver: Receiver<Command>) {
     let mut store = TicketStore::new();
-    loop {
-        match receiver.recv() {
-            Ok(Command::Insert {
-                draft,
-                response_channel,
-            }) => {
-                let id = store.add_ticket(draft);
-                response_channel.send(id).unwrap();
-            }
-            Ok(Command::Get {
-                id,
-                response_channel,
-            }) => {
-                let ticket = store.get(id);
-                response_channel.send(ticket.cloned()).unwrap();
-            }
-            Err(_) => {
-                // There are no more senders, so we can safely break
-                // and shut down the server.
-                break;
+    // `recv` fails once there are no more senders, so we can safely
+    // shut down the server.
+    while let Ok(command) = receiver.recv() {
+        let Command::DrainAndStop { response_channel } = command else {
+            handle(&mut store, command);
+            continue;
+        };
+        // Answer whatever is already queued, then hang up before
+        // acknowledging, so nothing sent afterwards is left waiting.
+        let mut stops = vec![response_channel];
+        while let Ok(command) = receiver.try_recv() {
+            match command {
+                Command::DrainAndStop { response_channel } => stops.push(response_channel),
+                command => handle(&mut store, command),
             }
         }
+        drop(receiver);
+        for stop in stops {
+            let _ = stop.send(());
+        }
+        return;
+    }
+}
+
+fn handle(store: &mut TicketStore, command: Command) {
+    match command {
+        Command::Insert {
+            draft,
+            response_channel,
+        } => {
+            let id = store.add_ticket(draft);
+            response_channel.send(id).unwrap();
+        }
+        Command::Get {
+            id,
+            response_channel,
+        } => {
+            let ticket = store.get(id);
+            response_channel.send(ticket.cloned()).unwrap();
+        }
+        Command::DrainAndStop { .. } => unreachable!("handled by the server loop"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use ticket_fields::test_helpers::{ticket_description, ticket_title};
+
+    fn draft() -> TicketDraft {
+        TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        }
+    }
+
+    #[test]
+    fn server_gone_after_panic() {
+        let (sender, receiver) = mpsc::sync_channel::<Command>(1);
+        let server = std::thread::spawn(move || {
+            let _receiver = receiver;
+            panic!("The server blew up");
+        });
+        while !server.is_finished() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let client = TicketStoreClient {
+            sender,
+            capacity: 1,
+            policy: BackpressurePolicy::Block,
+            server: Arc::new(server),
+        };
+
+        let id = TicketStore::new().add_ticket(draft());
+        assert_eq!(
+            client.insert(draft()),
+            Err(InsertError::ServerGone(ServerGone))
+        );
+        assert_eq!(client.get(id), Err(ServerGone));
+    }
+
+    #[test]
+    fn reports_configured_capacity() {
+        assert_eq!(launch(8).capacity(), 8);
+    }
+
+    #[test]
+    fn drain_and_stop_answers_queued_commands() {
+        let client = launch(8);
+        let responses: Vec<_> = (0..5)
+            .map(|_| {
+                let (tx, rx) = mpsc::sync_channel(1);
+                client
+                    .sender
+                    .send(Command::Insert {
+                        draft: draft(),
+                        response_channel: tx,
+                    })
+                    .unwrap();
+                rx
+            })
+            .collect();
+
+        client.drain_and_stop().unwrap();
+        let ids: Vec<TicketId> = responses.iter().map(|rx| rx.recv().unwrap()).collect();
+        assert_eq!(ids.len(), 5);
+        while !client.server.is_finished() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(client.get(ids[0]), Err(ServerGone));
+    }
+
+    // A client whose server doesn't read anything until `release` is sent,
+    // with its single queue slot already taken.
+    fn stalled(
+        policy: BackpressurePolicy,
+    ) -> (TicketStoreClient, SyncSender<()>, Receiver<TicketId>) {
+        let (sender, receiver) = mpsc::sync_channel(1);
+        let (release, gate) = mpsc::sync_channel::<()>(1);
+        let server = std::thread::spawn(move || {
+            let _ = gate.recv();
+            server(receiver)
+        });
+        let client = TicketStoreClient {
+            sender,
+            capacity: 1,
+            policy,
+            server: Arc::new(server),
+        };
+        let (tx, queued) = mpsc::sync_channel(1);
+        client
+            .sender
+            .send(Command::Insert {
+                draft: draft(),
+                response_channel: tx,
+            })
+            .unwrap();
+        (client, release, queued)
+    }
+
+    #[test]
+    fn block_waits_for_room() {
+        let (client, release, queued) = stalled(BackpressurePolicy::Block);
+        let inserter = {
+            let client = client.clone();
+            std::thread::spawn(move || client.insert(draft()))
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!inserter.is_finished());
+
+        release.send(()).unwrap();
+        let insertion = inserter.join().unwrap().unwrap();
+        let first = queued.recv().unwrap();
+        assert_ne!(insertion.id(), Some(first));
+        assert!(client.get(insertion.id().unwrap()).unwrap().is_some());
+    }
+
+    #[test]
+    fn drop_newest_reports_dropped_insert() {
+        let (client, release, queued) = stalled(BackpressurePolicy::DropNewest);
+        assert_eq!(client.insert(draft()), Ok(Insertion::Dropped));
+
+        release.send(()).unwrap();
+        queued.recv().unwrap();
+        assert!(matches!(client.insert(draft()), Ok(Insertion::Inserted(_))));
+    }
+
+    #[test]
+    fn error_policy_rejects_insert_when_full() {
+        let (client, release, queued) = stalled(BackpressurePolicy::Error);
+        assert_eq!(client.insert(draft()), Err(InsertError::Full));
+
+        release.send(()).unwrap();
+        queued.recv().unwrap();
+        assert!(matches!(client.insert(draft()), Ok(Insertion::Inserted(_))));
+    }
+
+    #[test]
+    fn launch_defaults_to_blocking() {
+        assert_eq!(launch(1).policy(), BackpressurePolicy::Block);
+        let client = launch_with_policy(1, BackpressurePolicy::Error);
+        assert_eq!(client.policy(), BackpressurePolicy::Error);
     }
 }
@@ -9,7 +9,7 @@ fn works() {
         title: ticket_title(),
         description: ticket_description(),
     };
-    let ticket_id = client.insert(draft.clone()).unwrap();
+    let ticket_id = client.insert(draft.clone()).unwrap().id().unwrap();
 
     let client2 = client.clone();
     let ticket = client2.get(ticket_id).unwrap().unwrap();
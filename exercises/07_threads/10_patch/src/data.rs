@@ -1,5 +1,5 @@
 use crate::store::TicketId;
-use ticket_fields::{TicketDescription, TicketTitle};
+use ticket_fields::{TicketDescription, TicketDescriptionError, TicketTitle, TicketTitleError};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Ticket {
@@ -15,6 +15,25 @@ pub struct TicketDraft {
     pub description: TicketDescription,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum TicketDraftError {
+    #[error(transparent)]
+    Title(#[from] TicketTitleError),
+    #[error(transparent)]
+    Description(#[from] TicketDescriptionError),
+}
+
+impl TicketDraft {
+    // Same limits as everywhere else: titles up to 50 bytes, descriptions up
+    // to 500, neither empty.
+    pub fn new(title: &str, description: &str) -> Result<Self, TicketDraftError> {
+        Ok(TicketDraft {
+            title: title.try_into()?,
+            description: description.try_into()?,
+        })
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TicketPatch {
     pub id: TicketId,
@@ -29,3 +48,36 @@ pub enum Status {
     InProgress,
     Done,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draft_accepts_lengths_up_to_the_limits() {
+        let draft = TicketDraft::new(&"t".repeat(50), &"d".repeat(500)).unwrap();
+        assert_eq!(draft.title, TicketTitle::try_from("t".repeat(50)).unwrap());
+        assert_eq!(
+            draft.description,
+            TicketDescription::try_from("d".repeat(500)).unwrap()
+        );
+    }
+
+    #[test]
+    fn draft_rejects_lengths_past_the_limits() {
+        assert!(matches!(
+            TicketDraft::new(&"t".repeat(51), "A description"),
+            Err(TicketDraftError::Title(TicketTitleError::TooLong))
+        ));
+        assert!(matches!(
+            TicketDraft::new("A title", &"d".repeat(501)),
+            Err(TicketDraftError::Description(
+                TicketDescriptionError::TooLong
+            ))
+        ));
+        assert!(matches!(
+            TicketDraft::new("", "A description"),
+            Err(TicketDraftError::Title(TicketTitleError::Empty))
+        ));
+    }
+}
@@ -1,4 +1,8 @@
-use std::sync::mpsc::{self, sync_channel, Receiver, SyncSender};
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{sync_channel, Receiver, SendError, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
 
 // TODO: Implement the patching functionality.
 use crate::data::{Ticket, TicketDraft, TicketPatch};
@@ -10,49 +14,102 @@ pub mod store;
 #[derive(Clone)]
 pub struct TicketStoreClient {
     sender: SyncSender<Command>,
+    server: Arc<JoinHandle<()>>,
 }
 
 impl TicketStoreClient {
-    pub fn insert(&self, draft: TicketDraft) -> Result<TicketId, OverloadedError> {
-        let (response_sender, response_receiver) = sync_channel(1);
-        self.sender
-            .try_send(Command::Insert {
-                draft,
-                response_channel: response_sender,
-            })
-            .map_err(|_| OverloadedError)?;
-        Ok(response_receiver.recv().unwrap())
+    pub fn insert(&self, draft: TicketDraft) -> Result<TicketId, RequestError> {
+        self.request(|response_channel| Command::Insert {
+            draft,
+            response_channel,
+        })
+    }
+
+    // Like `insert`, but hands back the stored ticket, saving a `get`.
+    pub fn insert_returning(&self, draft: TicketDraft) -> Result<Ticket, RequestError> {
+        self.request(|response_channel| Command::InsertReturning {
+            draft,
+            response_channel,
+        })
     }
 
-    pub fn get(&self, id: TicketId) -> Result<Option<Ticket>, OverloadedError> {
+    pub fn get(&self, id: TicketId) -> Result<Option<Ticket>, RequestError> {
+        self.request(|response_channel| Command::Get {
+            id,
+            response_channel,
+        })
+    }
+
+    pub fn update(&self, ticket_patch: TicketPatch) -> Result<(), RequestError> {
+        self.request(|response_channel| Command::Update {
+            patch: ticket_patch,
+            response_channel,
+        })
+    }
+
+    // The most recent error the server ran into, if any.
+    pub fn last_error(&self) -> Result<Option<String>, RequestError> {
+        self.request(|response_channel| Command::LastError { response_channel })
+    }
+
+    fn request<T>(
+        &self,
+        command: impl FnOnce(SyncSender<T>) -> Command,
+    ) -> Result<T, RequestError> {
+        self.ensure_server_alive()?;
         let (response_sender, response_receiver) = sync_channel(1);
-        self.sender
-            .try_send(Command::Get {
-                id,
-                response_channel: response_sender,
+        match self.sender.try_send(command(response_sender)) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => return Err(OverloadedError.into()),
+            Err(TrySendError::Disconnected(_)) => return Err(ServerGone.into()),
+        }
+        // Short of the server dying, the response channel is only dropped
+        // without a reply if handling the command panicked.
+        response_receiver
+            .recv()
+            .map_err(|_| match self.ensure_server_alive() {
+                Ok(()) => RequestError::Failed,
+                Err(gone) => gone.into(),
             })
-            .map_err(|_| OverloadedError)?;
-        Ok(response_receiver.recv().unwrap())
     }
 
-    pub fn update(&self, ticket_patch: TicketPatch) -> Result<(), OverloadedError> {
-        let (tx, rx ) = mpsc::sync_channel(1);
-        self.sender
-            .try_send(Command::Update { patch:ticket_patch, response_channel: tx })
-            .map_err(|_| OverloadedError)?;
-        Ok(rx.recv().unwrap())
-
+    // The server thread only finishes if it panicked outside a command or
+    // all senders are gone, and we're still holding one, so a finished
+    // thread means a dead server.
+    fn ensure_server_alive(&self) -> Result<(), ServerGone> {
+        if self.server.is_finished() {
+            Err(ServerGone)
+        } else {
+            Ok(())
+        }
     }
 }
 
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
 #[error("The store is overloaded")]
 pub struct OverloadedError;
 
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("The server thread is no longer running")]
+pub struct ServerGone;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum RequestError {
+    #[error(transparent)]
+    Overloaded(#[from] OverloadedError),
+    #[error(transparent)]
+    ServerGone(#[from] ServerGone),
+    #[error("The server failed to handle the request")]
+    Failed,
+}
+
 pub fn launch(capacity: usize) -> TicketStoreClient {
     let (sender, receiver) = sync_channel(capacity);
-    std::thread::spawn(move || server(receiver));
-    TicketStoreClient { sender }
+    let server = std::thread::spawn(move || server(receiver));
+    TicketStoreClient {
+        sender,
+        server: Arc::new(server),
+    }
 }
 
 enum Command {
@@ -60,6 +117,10 @@ enum Command {
         draft: TicketDraft,
         response_channel: SyncSender<TicketId>,
     },
+    InsertReturning {
+        draft: TicketDraft,
+        response_channel: SyncSender<Ticket>,
+    },
     Get {
         id: TicketId,
         response_channel: SyncSender<Option<Ticket>>,
@@ -68,49 +129,243 @@ enum Command {
         patch: TicketPatch,
         response_channel: SyncSender<()>,
     },
+    LastError {
+        response_channel: SyncSender<Option<String>>,
+    },
+}
+
+impl Command {
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Insert { .. } => "insert",
+            Command::InsertReturning { .. } => "insert returning",
+            Command::Get { .. } => "get",
+            Command::Update { .. } => "update",
+            Command::LastError { .. } => "last error",
+        }
+    }
+}
+
+// A failed send means the client hung up before reading its response.
+// That's not worth stopping the server for, but keep it around for debugging.
+fn record_error<T>(
+    last_error: &mut Option<String>,
+    command: &str,
+    result: Result<(), SendError<T>>,
+) {
+    if result.is_err() {
+        *last_error = Some(format!(
+            "Failed to respond to `{}`: the response channel was dropped",
+            command
+        ));
+    }
 }
 
 pub fn server(receiver: Receiver<Command>) {
+    serve_with(receiver, handle)
+}
+
+// `server`, but with the per-command work swapped out, so tests can make it
+// misbehave.
+fn serve_with(
+    receiver: Receiver<Command>,
+    mut handle: impl FnMut(&mut TicketStore, Command, &mut Option<String>),
+) {
     let mut store = TicketStore::new();
-    loop {
-        match receiver.recv() {
-            Ok(Command::Insert {
-                draft,
-                response_channel,
-            }) => {
-                let id = store.add_ticket(draft);
-                let _ = response_channel.send(id);
-            }
-            Ok(Command::Get {
-                id,
-                response_channel,
-            }) => {
-                let ticket = store.get(id);
-                let _ = response_channel.send(ticket.cloned());
-            }
-            Ok(Command::Update {
-                patch,
-                response_channel,
-            }) => {
-                if let Some(ticket) = store.get_mut(patch.id){
-                    if let Some(title) = patch.title{
-                        ticket.title = title
-                    }
-                    if let Some(description) = patch.description {
-                        ticket.description = description
-                    }
-                    if let Some(status) = patch.status {
-                        ticket.status = status
-                    }
+    // Only ever touched by this thread; clients ask for it with `LastError`.
+    let mut last_error = None;
+    // `recv` fails once there are no more senders, so we can safely
+    // shut down the server.
+    while let Ok(command) = receiver.recv() {
+        let name = command.name();
+        // A panicking command may leave its ticket half-patched, but that's
+        // better than taking every other client down with it.
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            handle(&mut store, command, &mut last_error)
+        }));
+        // The panic hook has already logged it. The command's response
+        // channel was dropped while unwinding, so its caller gets
+        // `RequestError::Failed` rather than waiting forever.
+        if let Err(payload) = result {
+            last_error = Some(format!(
+                "Panicked while handling `{}`: {}",
+                name,
+                panic_message(&payload)
+            ));
+        }
+    }
+}
+
+fn panic_message(payload: &Box<dyn Any + Send>) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+fn handle(store: &mut TicketStore, command: Command, last_error: &mut Option<String>) {
+    let name = command.name();
+    match command {
+        Command::Insert {
+            draft,
+            response_channel,
+        } => {
+            let id = store.add_ticket(draft);
+            record_error(last_error, name, response_channel.send(id));
+        }
+        Command::InsertReturning {
+            draft,
+            response_channel,
+        } => {
+            let id = store.add_ticket(draft);
+            let ticket = store.get(id).unwrap().clone();
+            record_error(last_error, name, response_channel.send(ticket));
+        }
+        Command::Get {
+            id,
+            response_channel,
+        } => {
+            let ticket = store.get(id);
+            record_error(last_error, name, response_channel.send(ticket.cloned()));
+        }
+        Command::Update {
+            patch,
+            response_channel,
+        } => {
+            if let Some(ticket) = store.get_mut(patch.id) {
+                if let Some(title) = patch.title {
+                    ticket.title = title
+                }
+                if let Some(description) = patch.description {
+                    ticket.description = description
+                }
+                if let Some(status) = patch.status {
+                    ticket.status = status
                 }
-                response_channel.send(());
-                
             }
-            Err(_) => {
-                // There are no more senders, so we can safely break
-                // and shut down the server.
-                break;
+            record_error(last_error, name, response_channel.send(()));
+        }
+        Command::LastError { response_channel } => {
+            let error = last_error.clone();
+            record_error(last_error, name, response_channel.send(error));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Status;
+    use std::time::Duration;
+    use ticket_fields::test_helpers::{ticket_description, ticket_title};
+    use ticket_fields::TicketTitle;
+
+    // Inserting a draft with this title makes `maybe_panic` panic, to check
+    // that a panicking command doesn't kill the server.
+    const PANIC_TITLE: &str = "Panic while inserting";
+
+    fn maybe_panic(store: &mut TicketStore, command: Command, last_error: &mut Option<String>) {
+        if let Command::Insert { draft, .. } = &command {
+            if draft.title == TicketTitle::try_from(PANIC_TITLE).unwrap() {
+                panic!("induced by a test");
             }
         }
+        handle(store, command, last_error)
+    }
+
+    fn launch_panicky(capacity: usize) -> TicketStoreClient {
+        let (sender, receiver) = sync_channel(capacity);
+        let server = std::thread::spawn(move || serve_with(receiver, maybe_panic));
+        TicketStoreClient {
+            sender,
+            server: Arc::new(server),
+        }
+    }
+
+    #[test]
+    fn last_error_records_dropped_response_channel() {
+        let client = launch(5);
+        assert_eq!(client.last_error().unwrap(), None);
+
+        let (response_sender, response_receiver) = sync_channel(1);
+        drop(response_receiver);
+        client
+            .sender
+            .send(Command::Insert {
+                draft: TicketDraft {
+                    title: ticket_title(),
+                    description: ticket_description(),
+                },
+                response_channel: response_sender,
+            })
+            .unwrap();
+
+        let error = client.last_error().unwrap().unwrap();
+        assert!(error.contains("insert"), "{}", error);
+    }
+
+    #[test]
+    fn server_survives_a_panicking_command() {
+        let client = launch_panicky(5);
+        let draft = TicketDraft {
+            title: TicketTitle::try_from(PANIC_TITLE).unwrap(),
+            description: ticket_description(),
+        };
+        assert_eq!(client.insert(draft), Err(RequestError::Failed));
+
+        let id = client
+            .insert(TicketDraft {
+                title: ticket_title(),
+                description: ticket_description(),
+            })
+            .unwrap();
+        assert!(client.get(id).unwrap().is_some());
+        let error = client.last_error().unwrap().unwrap();
+        assert!(error.contains("induced by a test"), "{}", error);
+    }
+
+    #[test]
+    fn server_gone_after_panic() {
+        let (sender, receiver) = sync_channel::<Command>(1);
+        let server = std::thread::spawn(move || {
+            let _receiver = receiver;
+            panic!("The server blew up");
+        });
+        while !server.is_finished() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let client = TicketStoreClient {
+            sender,
+            server: Arc::new(server),
+        };
+
+        let draft = TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        };
+        let id = TicketStore::new().add_ticket(draft.clone());
+        assert_eq!(
+            client.insert(draft),
+            Err(RequestError::ServerGone(ServerGone))
+        );
+        assert_eq!(client.get(id), Err(RequestError::ServerGone(ServerGone)));
+    }
+
+    #[test]
+    fn insert_returning_hands_back_the_stored_ticket() {
+        let client = launch(5);
+        let draft = TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        };
+
+        let ticket = client.insert_returning(draft.clone()).unwrap();
+        assert_eq!(ticket.title, draft.title);
+        assert_eq!(ticket.description, draft.description);
+        assert_eq!(ticket.status, Status::ToDo);
+        assert_eq!(client.get(ticket.id).unwrap(), Some(ticket));
     }
 }
@@ -1,25 +1,73 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, RwLock};
 
 use crate::data::{Status, Ticket, TicketDraft};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TicketId(u64);
 
+// The map a `TicketStore` keeps its tickets in. `BTreeMap` keeps them
+// ordered by id, which is what list or range queries want; `HashMap` trades
+// that ordering for cheaper point lookups.
+pub trait TicketMap: Default {
+    fn insert(&mut self, id: TicketId, ticket: Arc<RwLock<Ticket>>);
+    fn get(&self, id: &TicketId) -> Option<&Arc<RwLock<Ticket>>>;
+    fn remove(&mut self, id: &TicketId) -> Option<Arc<RwLock<Ticket>>>;
+}
+
+impl TicketMap for BTreeMap<TicketId, Arc<RwLock<Ticket>>> {
+    fn insert(&mut self, id: TicketId, ticket: Arc<RwLock<Ticket>>) {
+        BTreeMap::insert(self, id, ticket);
+    }
+
+    fn get(&self, id: &TicketId) -> Option<&Arc<RwLock<Ticket>>> {
+        BTreeMap::get(self, id)
+    }
+
+    fn remove(&mut self, id: &TicketId) -> Option<Arc<RwLock<Ticket>>> {
+        BTreeMap::remove(self, id)
+    }
+}
+
+impl TicketMap for HashMap<TicketId, Arc<RwLock<Ticket>>> {
+    fn insert(&mut self, id: TicketId, ticket: Arc<RwLock<Ticket>>) {
+        HashMap::insert(self, id, ticket);
+    }
+
+    fn get(&self, id: &TicketId) -> Option<&Arc<RwLock<Ticket>>> {
+        HashMap::get(self, id)
+    }
+
+    fn remove(&mut self, id: &TicketId) -> Option<Arc<RwLock<Ticket>>> {
+        HashMap::remove(self, id)
+    }
+}
+
+pub type BTreeTicketStore = TicketStore<BTreeMap<TicketId, Arc<RwLock<Ticket>>>>;
+pub type HashTicketStore = TicketStore<HashMap<TicketId, Arc<RwLock<Ticket>>>>;
+
 #[derive(Clone)]
-pub struct TicketStore {
-    tickets: BTreeMap<TicketId, Arc<RwLock<Ticket>>>,
+pub struct TicketStore<M = BTreeMap<TicketId, Arc<RwLock<Ticket>>>> {
+    tickets: M,
     counter: u64,
 }
 
 impl TicketStore {
     pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<M: TicketMap> Default for TicketStore<M> {
+    fn default() -> Self {
         Self {
-            tickets: BTreeMap::new(),
+            tickets: M::default(),
             counter: 0,
         }
     }
+}
 
+impl<M: TicketMap> TicketStore<M> {
     pub fn add_ticket(&mut self, ticket: TicketDraft) -> TicketId {
         let id = TicketId(self.counter);
         self.counter += 1;
@@ -37,4 +85,8 @@ impl TicketStore {
     pub fn get(&self, id: TicketId) -> Option<Arc<RwLock<Ticket>>> {
         self.tickets.get(&id).cloned()
     }
+
+    pub fn remove(&mut self, id: TicketId) -> Option<Arc<RwLock<Ticket>>> {
+        self.tickets.remove(&id)
+    }
 }
@@ -0,0 +1,41 @@
+use ticket_fields::test_helpers::{ticket_description, ticket_title};
+use without_channels::data::{Status, TicketDraft};
+use without_channels::store::{BTreeTicketStore, HashTicketStore, TicketMap, TicketStore};
+
+fn check_backing<M: TicketMap>(mut store: TicketStore<M>) {
+    let draft = TicketDraft {
+        title: ticket_title(),
+        description: ticket_description(),
+    };
+    let id1 = store.add_ticket(draft.clone());
+    let id2 = store.add_ticket(draft.clone());
+    assert_ne!(id1, id2);
+
+    let ticket = store.get(id1).unwrap();
+    let ticket = ticket.read().unwrap();
+    assert_eq!(ticket.id, id1);
+    assert_eq!(ticket.title, draft.title);
+    assert_eq!(ticket.description, draft.description);
+    assert_eq!(ticket.status, Status::ToDo);
+
+    let removed = store.remove(id1).unwrap();
+    assert_eq!(removed.read().unwrap().id, id1);
+    assert!(store.get(id1).is_none());
+    assert!(store.remove(id1).is_none());
+    assert!(store.get(id2).is_some());
+}
+
+#[test]
+fn btree_backing() {
+    check_backing(BTreeTicketStore::default());
+}
+
+#[test]
+fn hash_backing() {
+    check_backing(HashTicketStore::default());
+}
+
+#[test]
+fn default_backing_is_btree() {
+    let _: BTreeTicketStore = TicketStore::new();
+}
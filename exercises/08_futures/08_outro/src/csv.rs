@@ -0,0 +1,110 @@
+// Just enough CSV (RFC 4180 style) for exporting and importing tickets.
+
+// Quote a CSV field if it contains a separator, a quote or a line break,
+// doubling any embedded quotes.
+pub(crate) fn escape_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Splits `input` into records, one `Result` per record so a malformed row
+// doesn't stop the ones after it from being read. Quoted fields may span
+// lines; blank lines are skipped.
+pub(crate) fn parse_records(input: &str) -> Vec<Result<Vec<String>, String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut error = None;
+    let mut in_quotes = false;
+    // Set right after a closing quote, where only `,` or a line break may follow.
+    let mut after_quotes = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => {
+                    in_quotes = false;
+                    after_quotes = true;
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            ',' => {
+                record.push(std::mem::take(&mut field));
+                after_quotes = false;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                let finished = std::mem::take(&mut record);
+                let is_blank = finished.len() == 1 && finished[0].is_empty() && !after_quotes;
+                match error.take() {
+                    Some(error) => records.push(Err(error)),
+                    None if is_blank => {}
+                    None => records.push(Ok(finished)),
+                }
+                after_quotes = false;
+            }
+            _ if error.is_some() => {}
+            _ if after_quotes => {
+                error = Some(format!("unexpected `{}` after a closing quote", c));
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            '"' => error = Some("unexpected `\"` inside an unquoted field".to_string()),
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        records.push(Err("unterminated quoted field".to_string()));
+    } else if let Some(error) = error {
+        records.push(Err(error));
+    } else if !record.is_empty() || !field.is_empty() || after_quotes {
+        record.push(field);
+        records.push(Ok(record));
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_field() {
+        assert_eq!(escape_field("plain"), "plain");
+        assert_eq!(escape_field("a, b"), "\"a, b\"");
+        assert_eq!(escape_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+    #[test]
+    fn test_parse_records() {
+        let input = "a,b\n\"c, d\",\"e \"\"f\"\"\"\n\ng,\"multi\nline\"";
+        assert_eq!(
+            parse_records(input),
+            vec![
+                Ok(vec!["a".to_string(), "b".to_string()]),
+                Ok(vec!["c, d".to_string(), "e \"f\"".to_string()]),
+                Ok(vec!["g".to_string(), "multi\nline".to_string()]),
+            ]
+        );
+    }
+    #[test]
+    fn test_parse_malformed_records() {
+        let input = "\"a\"b,c\nd,e\n\"unterminated";
+        let records = parse_records(input);
+        assert!(records[0].is_err());
+        assert_eq!(records[1], Ok(vec!["d".to_string(), "e".to_string()]));
+        assert!(records[2].is_err());
+        assert_eq!(records.len(), 3);
+    }
+}
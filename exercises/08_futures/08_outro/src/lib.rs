@@ -8,5 +8,10 @@
 //
 // Use Rust's package registry, crates.io, to find the dependencies you need
 // (if any) to build this system.
+mod csv;
+pub mod page;
 pub mod ticket;
-pub mod ticket_store;
\ No newline at end of file
+pub mod ticket_actor;
+pub mod ticket_client;
+pub mod ticket_lock;
+pub mod ticket_store;
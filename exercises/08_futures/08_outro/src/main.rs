@@ -1,39 +1,108 @@
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use axum::{
-    extract::{Json, State},
-    response::IntoResponse,
+    extract::{Extension, Json, Path, Query, State},
+    http::{header, HeaderMap},
+    response::sse::{Event, KeepAlive, Sse},
+    response::{IntoResponse, Response},
     routing::{get, patch, post},
     Router,
 };
+use futures_util::stream::{self, Stream, StreamExt};
 use hyper::StatusCode;
-use outro_08::ticket_store::TicketStore;
+use outro_08::page::Page;
+use outro_08::ticket_lock::{Locked, TicketLocks};
+use outro_08::ticket_store::{InsertError, TicketEvent, TicketStore};
 use outro_08::{
     ticket::{Ticket, TicketId, TicketParseError, TicketPatch},
     ticket_store::PatchError,
 };
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use thiserror::Error;
+use tokio::sync::broadcast::error::RecvError;
 #[derive(Debug, Error)]
-struct AppError(#[source] anyhow::Error);
+struct AppError {
+    status: StatusCode,
+    #[source]
+    source: anyhow::Error,
+}
+
+impl AppError {
+    fn bad_request(err: impl Into<anyhow::Error>) -> Self {
+        AppError {
+            status: StatusCode::BAD_REQUEST,
+            source: err.into(),
+        }
+    }
+    fn not_found(err: impl Into<anyhow::Error>) -> Self {
+        AppError {
+            status: StatusCode::NOT_FOUND,
+            source: err.into(),
+        }
+    }
+    fn conflict(err: impl Into<anyhow::Error>) -> Self {
+        AppError {
+            status: StatusCode::CONFLICT,
+            source: err.into(),
+        }
+    }
+    fn locked(err: impl Into<anyhow::Error>) -> Self {
+        AppError {
+            status: StatusCode::LOCKED,
+            source: err.into(),
+        }
+    }
+}
 
 impl std::fmt::Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.source)
     }
 }
 impl IntoResponse for AppError {
     fn into_response(self) -> axum::response::Response {
-        let error_msg = self.0.to_string();
-        let status = StatusCode::BAD_REQUEST;
-        let body = Json(json!({"error": error_msg}));
-        (status, body).into_response()
+        let error_msg = self.source.to_string();
+        // Derived from the status, e.g. `NOT_FOUND` for a 404, so clients
+        // can tell errors apart without matching on the message.
+        let code = self
+            .status
+            .canonical_reason()
+            .unwrap_or("ERROR")
+            .to_uppercase()
+            .replace(' ', "_");
+        let body = Json(json!({"error": {"code": code, "message": error_msg}}));
+        (self.status, body).into_response()
     }
 }
 
 impl From<PatchError> for AppError {
     fn from(err: PatchError) -> Self {
-        AppError(anyhow::anyhow!(err))
+        match err {
+            PatchError::NotFound(_) => AppError::not_found(err),
+            PatchError::Mismatch(_) | PatchError::Transition { .. } => AppError::conflict(err),
+            PatchError::UnknownStatus(_) => AppError::bad_request(err),
+        }
+    }
+}
+
+impl From<InsertError> for AppError {
+    fn from(err: InsertError) -> Self {
+        AppError::bad_request(err)
+    }
+}
+
+impl From<Locked> for AppError {
+    fn from(err: Locked) -> Self {
+        AppError::locked(err)
+    }
+}
+
+impl From<TicketParseError> for AppError {
+    fn from(err: TicketParseError) -> Self {
+        AppError::bad_request(err)
     }
 }
 
@@ -43,48 +112,380 @@ async fn create_ticket(
     Json(ticket): Json<Ticket>,
 ) -> Result<Json<Value>, AppError> {
     let tid = ticket.id;
-    store.insert(ticket);
+    store.insert(ticket)?;
     Ok(Json(json!({"id": tid})))
 }
+// How a handler should render what it returns, picked from `Accept`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Json,
+    Text,
+}
+impl Format {
+    // The first media type we can produce wins; quality values are ignored
+    // and anything we don't recognise falls back to JSON.
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let Some(accept) = headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+        else {
+            return Format::Json;
+        };
+        for media_type in accept.split(',') {
+            match media_type.split(';').next().unwrap_or_default().trim() {
+                "text/plain" => return Format::Text,
+                "application/json" | "application/*" | "*/*" => return Format::Json,
+                _ => {}
+            }
+        }
+        Format::Json
+    }
+    fn render<T: serde::Serialize + std::fmt::Display>(self, value: T) -> Response {
+        match self {
+            Format::Json => Json(value).into_response(),
+            Format::Text => value.to_string().into_response(),
+        }
+    }
+}
+#[derive(Debug, Deserialize)]
+struct GetOptions {
+    // Comma-separated ticket fields to return, e.g. `id,status`.
+    fields: Option<String>,
+}
+// Only the named fields of `ticket`, under their usual JSON names.
+fn project(ticket: &Ticket, fields: &str) -> Result<Value, AppError> {
+    let all = serde_json::to_value(ticket).expect("tickets always serialize");
+    let mut selected = serde_json::Map::new();
+    for field in fields.split(',').map(str::trim) {
+        let Some(value) = all.get(field) else {
+            return Err(AppError::bad_request(anyhow::anyhow!(
+                "Unknown ticket field `{}`",
+                field
+            )));
+        };
+        selected.insert(field.to_string(), value.clone());
+    }
+    Ok(Value::Object(selected))
+}
+// With `fields`, the reply is always a JSON object, whatever `Accept` says.
 #[axum::debug_handler]
 async fn get_ticket(
     State(store): State<TicketStore>,
+    headers: HeaderMap,
+    Query(options): Query<GetOptions>,
     Json(id): Json<TicketId>,
-) -> Result<Json<Ticket>, AppError> {
+) -> Result<Response, AppError> {
     let ticket = store.get(id);
-    match ticket {
-        Some(ticket) => Ok(Json(ticket)),
-        None => Err(AppError(anyhow::anyhow!("Ticket not found"))),
+    match (ticket, options.fields) {
+        (Some(ticket), Some(fields)) => Ok(Json(project(&ticket, &fields)?).into_response()),
+        (Some(ticket), None) => Ok(Format::from_headers(&headers).render(ticket)),
+        (None, _) => Err(AppError::bad_request(anyhow::anyhow!("Ticket not found"))),
     }
 }
+// How long a `POST /tickets/{id}/lock` lasts.
+const LOCK_TTL: Duration = Duration::from_secs(30);
+// Carries the token from `POST /tickets/{id}/lock` on a `PATCH`.
+const LOCK_TOKEN: &str = "Lock-Token";
+// Fails with `423 Locked` while another client holds the ticket's lock.
+fn check_lock(locks: &TicketLocks, id: TicketId, headers: &HeaderMap) -> Result<(), Locked> {
+    let token = headers
+        .get(LOCK_TOKEN)
+        .and_then(|value| value.to_str().ok());
+    locks.check(id, token, Instant::now())
+}
 #[axum::debug_handler]
 async fn update_ticket(
     State(store): State<TicketStore>,
+    Extension(locks): Extension<TicketLocks>,
+    headers: HeaderMap,
     Json(patch): Json<TicketPatch>,
 ) -> Result<(), AppError> {
+    check_lock(&locks, patch.id, &headers)?;
     store.patch(patch.id, patch)?;
     Ok(())
 }
+// The id in the path must match the one in the patch body.
+#[axum::debug_handler]
+async fn update_ticket_by_id(
+    State(store): State<TicketStore>,
+    Extension(locks): Extension<TicketLocks>,
+    Path(id): Path<TicketId>,
+    headers: HeaderMap,
+    Json(patch): Json<TicketPatch>,
+) -> Result<(), AppError> {
+    check_lock(&locks, id, &headers)?;
+    store.patch(id, patch)?;
+    Ok(())
+}
+// Until it expires, only `PATCH` requests sending the returned token in
+// `Lock-Token` can edit the ticket.
+#[axum::debug_handler]
+async fn lock_ticket(
+    State(store): State<TicketStore>,
+    Extension(locks): Extension<TicketLocks>,
+    Path(id): Path<TicketId>,
+) -> Result<Json<Value>, AppError> {
+    if store.get(id).is_none() {
+        return Err(AppError::not_found(anyhow::anyhow!(
+            "Ticket {} not found",
+            id
+        )));
+    }
+    let token = locks.acquire(id, Instant::now())?;
+    Ok(Json(
+        json!({"token": token, "ttl_secs": locks.ttl().as_secs()}),
+    ))
+}
+#[axum::debug_handler]
+async fn duplicate_ticket(
+    State(store): State<TicketStore>,
+    Path(id): Path<TicketId>,
+) -> Result<Json<Value>, AppError> {
+    match store.duplicate(id) {
+        Some(new_id) => Ok(Json(json!({"id": new_id}))),
+        None => Err(AppError::not_found(anyhow::anyhow!(
+            "Ticket {} not found",
+            id
+        ))),
+    }
+}
+// `status` is required so a bare `DELETE /tickets` can't wipe the store.
+#[derive(Debug, Deserialize)]
+struct DeleteByStatus {
+    status: String,
+}
+#[axum::debug_handler]
+async fn delete_tickets_by_status(
+    State(store): State<TicketStore>,
+    Query(query): Query<DeleteByStatus>,
+) -> Result<Json<Value>, AppError> {
+    let status = store.parse_status(&query.status)?;
+    let deleted = store.delete_by_status(status);
+    Ok(Json(json!({"deleted": deleted})))
+}
+#[derive(Debug, Deserialize)]
+struct ImportOptions {
+    #[serde(default)]
+    strict: bool,
+}
+#[axum::debug_handler]
+async fn import_tickets_csv(
+    State(store): State<TicketStore>,
+    Query(options): Query<ImportOptions>,
+    body: String,
+) -> impl IntoResponse {
+    let summary = store.import_csv(&body, options.strict);
+    let status = if options.strict && !summary.rejected.is_empty() {
+        StatusCode::BAD_REQUEST
+    } else {
+        StatusCode::OK
+    };
+    (status, Json(summary))
+}
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ListOrder {
+    #[default]
+    Id,
+    Random,
+}
+// Page size when `GET /tickets` doesn't ask for one.
+const DEFAULT_PAGE_LIMIT: usize = 100;
+
+fn default_page_limit() -> usize {
+    DEFAULT_PAGE_LIMIT
+}
+#[derive(Debug, Deserialize)]
+struct ListOptions {
+    #[serde(default)]
+    order: ListOrder,
+    // Only used by `order=random`.
+    #[serde(default)]
+    seed: u64,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default = "default_page_limit")]
+    limit: usize,
+}
+#[axum::debug_handler]
+async fn list_tickets(
+    State(store): State<TicketStore>,
+    Query(options): Query<ListOptions>,
+) -> Json<Page<Arc<Ticket>>> {
+    let ListOptions {
+        order,
+        seed,
+        offset,
+        limit,
+    } = options;
+    match order {
+        ListOrder::Id => Json(store.page(offset, limit)),
+        // Pages of the same shuffle, as long as the tickets don't change.
+        ListOrder::Random => Json(Page::window(store.list_shuffled(seed), offset, limit)),
+    }
+}
+#[axum::debug_handler]
+async fn export_tickets_csv(State(store): State<TicketStore>) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/csv")], store.to_csv())
+}
+#[derive(Debug, Deserialize)]
+struct MetricsOptions {
+    // `json` for a JSON object; anything else gets the Prometheus text format.
+    format: Option<String>,
+}
+#[axum::debug_handler]
+async fn metrics(
+    State(store): State<TicketStore>,
+    Query(options): Query<MetricsOptions>,
+) -> Response {
+    let metrics = store.metrics();
+    if options.format.as_deref() == Some("json") {
+        return Json(metrics).into_response();
+    }
+    let body = format!(
+        "# TYPE ticket_inserts_total counter\n\
+         ticket_inserts_total {}\n\
+         # TYPE ticket_updates_total counter\n\
+         ticket_updates_total {}\n\
+         # TYPE ticket_deletes_total counter\n\
+         ticket_deletes_total {}\n\
+         # TYPE tickets gauge\n\
+         tickets {}\n",
+        metrics.inserts_total, metrics.updates_total, metrics.deletes_total, metrics.tickets
+    );
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
+}
+// Limits past which `GET /health` stops reporting `ok`; `None` turns a
+// check off.
+#[derive(Debug, Clone, Default)]
+struct HealthThresholds {
+    // More tickets than this is `degraded`...
+    degraded_tickets: Option<usize>,
+    // ...and more than this is `unhealthy`.
+    unhealthy_tickets: Option<usize>,
+    // More events than this waiting on a slow subscriber is `degraded`.
+    degraded_event_backlog: Option<usize>,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum HealthStatus {
+    Ok,
+    Degraded,
+    Unhealthy,
+}
+// `degraded` still answers 200 so load balancers keep routing here; only
+// `unhealthy` answers 503. `reasons` says which thresholds were crossed.
+#[axum::debug_handler]
+async fn health(
+    State(store): State<TicketStore>,
+    Extension(thresholds): Extension<HealthThresholds>,
+) -> Response {
+    let tickets = store.metrics().tickets;
+    let event_backlog = store.event_backlog();
+    let exceeded = |limit: Option<usize>, value: usize| limit.filter(|&limit| value > limit);
+
+    let mut status = HealthStatus::Ok;
+    let mut reasons = Vec::new();
+    if let Some(limit) = exceeded(thresholds.unhealthy_tickets, tickets) {
+        status = HealthStatus::Unhealthy;
+        reasons.push(format!(
+            "{} tickets stored, unhealthy above {}",
+            tickets, limit
+        ));
+    } else if let Some(limit) = exceeded(thresholds.degraded_tickets, tickets) {
+        status = HealthStatus::Degraded;
+        reasons.push(format!(
+            "{} tickets stored, degraded above {}",
+            tickets, limit
+        ));
+    }
+    if let Some(limit) = exceeded(thresholds.degraded_event_backlog, event_backlog) {
+        status = status.max(HealthStatus::Degraded);
+        reasons.push(format!(
+            "{} events waiting on subscribers, degraded above {}",
+            event_backlog, limit
+        ));
+    }
+
+    let code = if status == HealthStatus::Unhealthy {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    let body = json!({
+        "status": status,
+        "tickets": tickets,
+        "event_backlog": event_backlog,
+        "reasons": reasons,
+    });
+    (code, Json(body)).into_response()
+}
+// Sends every stored ticket as a `snapshot` event, then live `created`,
+// `updated` and `deleted` events as they happen.
+#[axum::debug_handler]
+async fn stream_tickets(
+    State(store): State<TicketStore>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    // Subscribe before taking the snapshot so nothing falls in between.
+    let events = store.subscribe();
+    let snapshot = stream::iter(store.list())
+        .map(|ticket| Event::default().event("snapshot").json_data(ticket));
+    let live = stream::unfold(events, |mut events| async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => return Some((event, events)),
+                // A slow client skips what it missed rather than disconnecting.
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    })
+    .map(|event| match event {
+        TicketEvent::Created(ticket) => Event::default().event("created").json_data(ticket),
+        TicketEvent::Updated(ticket) => Event::default().event("updated").json_data(ticket),
+        TicketEvent::Deleted(id) => Event::default().event("deleted").json_data(id),
+    });
+    Sse::new(snapshot.chain(live)).keep_alive(KeepAlive::default())
+}
+
+// Unknown paths get the same JSON error body as every other failure.
+async fn not_found(uri: axum::http::Uri) -> AppError {
+    AppError::not_found(anyhow::anyhow!("No route for {}", uri.path()))
+}
 
 fn get_app() -> Router {
-    let store = TicketStore::new();
-    let app = Router::new()
+    build_app(TicketStore::new(), HealthThresholds::default())
+}
+
+fn build_app(store: TicketStore, health_thresholds: HealthThresholds) -> Router {
+    Router::new()
         .route(
             "/ticket",
             post(create_ticket).get(get_ticket).patch(update_ticket),
         )
-        .with_state(store);
-    app
+        .route(
+            "/tickets",
+            get(list_tickets).delete(delete_tickets_by_status),
+        )
+        .route("/health", get(health))
+        .route("/metrics", get(metrics))
+        .route("/tickets.csv", get(export_tickets_csv))
+        .route("/tickets/import.csv", post(import_tickets_csv))
+        .route("/tickets/{id}", patch(update_ticket_by_id))
+        .route("/tickets/{id}/duplicate", post(duplicate_ticket))
+        .route("/tickets/{id}/lock", post(lock_ticket))
+        .route("/tickets/stream", get(stream_tickets))
+        .fallback(not_found)
+        .layer(Extension(health_thresholds))
+        .layer(Extension(TicketLocks::new(LOCK_TTL)))
+        .with_state(store)
 }
 
 #[tokio::main]
 async fn main() {
-    let app = Router::new().route(
-        "/ticket",
-        post(create_ticket).get(get_ticket).patch(update_ticket),
-    );
-    let store = TicketStore::new();
-    let app = app.with_state(store);
+    // Without a subscriber every `tracing` event is dropped.
+    tracing_subscriber::fmt::init();
+    let app = get_app();
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
     let listener = tokio::net::TcpListener::bind(addr)
         .await
@@ -98,14 +499,17 @@ async fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use outro_08::ticket::{CustomStatus, TicketStatus};
 
     async fn setup_server() -> String {
+        serve(get_app()).await
+    }
+    async fn serve(app: Router) -> String {
         let addr = SocketAddr::from(([127, 0, 0, 1], 0));
         let listener = tokio::net::TcpListener::bind(addr)
             .await
             .expect("Failed to bind TCP listener");
         let used_addr = listener.local_addr().unwrap();
-        let app = get_app();
         tokio::spawn(async move {
             axum::serve(listener, app.into_make_service())
                 .await
@@ -113,23 +517,31 @@ mod tests {
         });
         format!("http://{}", used_addr)
     }
-    #[tokio::test]
-    async fn test_create_get_ticket() {
-        let server_url = setup_server().await;
-        let client = reqwest::Client::new();
-        let ticket = Ticket::new(
-            42.into(),
+    fn sample_ticket(id: u64, status: &str) -> Ticket {
+        Ticket::new(
+            id.into(),
             "this is a title".try_into().unwrap(),
             "this is a description".try_into().unwrap(),
-            "todo".try_into().unwrap(),
-        );
+            status.try_into().unwrap(),
+        )
+    }
+    // Stores `sample_ticket(id, status)` through the API and returns it.
+    async fn create_ticket(client: &reqwest::Client, url: &str, id: u64, status: &str) -> Ticket {
+        let ticket = sample_ticket(id, status);
         let response = client
-            .post(format!("{}/ticket", server_url))
+            .post(format!("{}/ticket", url))
             .json(&ticket)
             .send()
             .await
             .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
+        ticket
+    }
+    #[tokio::test]
+    async fn test_create_get_ticket() {
+        let server_url = setup_server().await;
+        let client = reqwest::Client::new();
+        let ticket = create_ticket(&client, &server_url, 42, "todo").await;
         let get_response = client
             .get(format!("{}/ticket", server_url))
             .json(&ticket.id)
@@ -142,22 +554,39 @@ mod tests {
         eprintln!("{:?}", fetched_ticket);
     }
     #[tokio::test]
-    async fn test_patch_ticket(){
+    async fn test_get_ticket_content_negotiation() {
         let server_url = setup_server().await;
         let client = reqwest::Client::new();
-        let ticket = Ticket::new(
-            42.into(),
-            "this is a title".try_into().unwrap(),
-            "this is a description".try_into().unwrap(),
-            "todo".try_into().unwrap(),
-        );
-        let response = client
-            .post(format!("{}/ticket", server_url))
-            .json(&ticket)
-            .send()
-            .await
-            .unwrap();
+        let ticket = create_ticket(&client, &server_url, 42, "todo").await;
+        let get_as = |accept: &'static str| {
+            client
+                .get(format!("{}/ticket", server_url))
+                .header(header::ACCEPT, accept)
+                .json(&ticket.id)
+                .send()
+        };
+
+        let response = get_as("text/plain").await.unwrap();
         assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain"));
+        assert_eq!(response.text().await.unwrap(), ticket.to_string());
+
+        for accept in ["application/json", "image/png", "text/html, */*;q=0.8"] {
+            let response = get_as(accept).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+            let fetched: Ticket = response.json().await.unwrap();
+            assert_eq!(fetched, ticket);
+        }
+    }
+    #[tokio::test]
+    async fn test_patch_ticket(){
+        let server_url = setup_server().await;
+        let client = reqwest::Client::new();
+        let ticket = create_ticket(&client, &server_url, 42, "todo").await;
         let patch = TicketPatch::new(ticket.id, None, None, Some("done".try_into().unwrap()));
         let patch_response = client
             .patch(format!("{}/ticket", server_url))
@@ -177,4 +606,490 @@ mod tests {
         assert_eq!(fetched_ticket.status, "done".try_into().unwrap());
         eprintln!("patch test output:\n{:?}", fetched_ticket);
     }
+    #[tokio::test]
+    async fn test_export_tickets_csv() {
+        let server_url = setup_server().await;
+        let client = reqwest::Client::new();
+        create_ticket(&client, &server_url, 42, "todo").await;
+        let response = client
+            .get(format!("{}/tickets.csv", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/csv");
+        assert_eq!(
+            response.text().await.unwrap(),
+            "id,status,title,description\n42,todo,this is a title,this is a description\n"
+        );
+    }
+    #[tokio::test]
+    async fn test_stream_tickets() {
+        let server_url = setup_server().await;
+        let client = reqwest::Client::new();
+        let ticket = create_ticket(&client, &server_url, 42, "todo").await;
+
+        let mut stream = client
+            .get(format!("{}/tickets/stream", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(stream.headers()[header::CONTENT_TYPE], "text/event-stream");
+        let mut received = String::new();
+        while !received.contains("event: snapshot") {
+            let chunk = stream.chunk().await.unwrap().unwrap();
+            received.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        assert!(received.contains(r#""id":42"#));
+
+        let mut new_ticket = ticket.clone();
+        new_ticket.id = 43.into();
+        client
+            .post(format!("{}/ticket", server_url))
+            .json(&new_ticket)
+            .send()
+            .await
+            .unwrap();
+        let mut received = String::new();
+        while !received.contains("event: created") {
+            let chunk = stream.chunk().await.unwrap().unwrap();
+            received.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+        assert!(received.contains(r#""id":43"#));
+    }
+    #[tokio::test]
+    async fn test_delete_tickets_by_status() {
+        let server_url = setup_server().await;
+        let client = reqwest::Client::new();
+        for (id, status) in [(1, "done"), (2, "todo"), (3, "done")] {
+            create_ticket(&client, &server_url, id, status).await;
+        }
+
+        let response = client
+            .delete(format!("{}/tickets", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = client
+            .delete(format!("{}/tickets?status=done", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body, json!({"deleted": 2}));
+
+        let get_response = client
+            .get(format!("{}/ticket", server_url))
+            .json(&TicketId::from(2))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(get_response.status(), StatusCode::OK);
+    }
+    #[tokio::test]
+    async fn test_delete_tickets_by_custom_status() {
+        let store =
+            TicketStore::new().with_custom_statuses([CustomStatus::new("blocked").unwrap()]);
+        let server_url = serve(build_app(store, HealthThresholds::default())).await;
+        let client = reqwest::Client::new();
+        create_ticket(&client, &server_url, 1, "todo").await;
+        let mut blocked = sample_ticket(2, "todo");
+        blocked.status = TicketStatus::Custom(CustomStatus::new("blocked").unwrap());
+        let response = client
+            .post(format!("{}/ticket", server_url))
+            .json(&blocked)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = client
+            .delete(format!("{}/tickets?status=blocked", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body, json!({"deleted": 1}));
+
+        let response = client
+            .delete(format!("{}/tickets?status=limbo", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    #[tokio::test]
+    async fn test_duplicate_ticket() {
+        let server_url = setup_server().await;
+        let client = reqwest::Client::new();
+        let ticket = create_ticket(&client, &server_url, 42, "done").await;
+
+        let response = client
+            .post(format!("{}/tickets/42/duplicate", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        let copy_id: TicketId = serde_json::from_value(body["id"].clone()).unwrap();
+        assert_ne!(copy_id, ticket.id);
+
+        let get_response = client
+            .get(format!("{}/ticket", server_url))
+            .json(&copy_id)
+            .send()
+            .await
+            .unwrap();
+        let copy: Ticket = get_response.json().await.unwrap();
+        assert_eq!(copy.id, copy_id);
+        assert_eq!(copy.title, ticket.title);
+        assert_eq!(copy.description, ticket.description);
+        assert_eq!(copy.status, "todo".try_into().unwrap());
+
+        let response = client
+            .post(format!("{}/tickets/7/duplicate", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+    #[tokio::test]
+    async fn test_import_tickets_csv() {
+        let server_url = setup_server().await;
+        let client = reqwest::Client::new();
+        let csv = "id,status,title,description\n1,todo,a title,a description\n2,todo,,oops\n";
+
+        let response = client
+            .post(format!("{}/tickets/import.csv?strict=true", server_url))
+            .body(csv)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = client
+            .post(format!("{}/tickets/import.csv", server_url))
+            .body(csv)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let summary: Value = response.json().await.unwrap();
+        assert_eq!(summary["imported"], 1);
+        assert_eq!(summary["rejected"][0]["row"], 3);
+
+        let get_response = client
+            .get(format!("{}/ticket", server_url))
+            .json(&TicketId::from(1))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(get_response.status(), StatusCode::OK);
+    }
+    #[tokio::test]
+    async fn test_list_tickets_order() {
+        let server_url = setup_server().await;
+        let client = reqwest::Client::new();
+        for id in 0..10 {
+            create_ticket(&client, &server_url, id, "todo").await;
+        }
+        let list = |query: &'static str| {
+            let request = client.get(format!("{}/tickets{}", server_url, query));
+            async move {
+                let response = request.send().await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let page: Page<Ticket> = response.json().await.unwrap();
+                page.items
+                    .into_iter()
+                    .map(|ticket| ticket.id)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let by_id: Vec<TicketId> = (0..10).map(TicketId::from).collect();
+        assert_eq!(list("").await, by_id);
+        let seeded = list("?order=random&seed=7").await;
+        assert_eq!(seeded, list("?order=random&seed=7").await);
+        assert_ne!(seeded, list("?order=random&seed=8").await);
+        assert_ne!(seeded, by_id);
+
+        let response = client
+            .get(format!("{}/tickets?order=sideways", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    #[tokio::test]
+    async fn test_list_tickets_page() {
+        let server_url = setup_server().await;
+        let client = reqwest::Client::new();
+        for id in 0..10 {
+            create_ticket(&client, &server_url, id, "todo").await;
+        }
+
+        let response = client
+            .get(format!("{}/tickets?offset=3&limit=4", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["total"], json!(10));
+        assert_eq!(body["offset"], json!(3));
+        assert_eq!(body["limit"], json!(4));
+        let ids: Vec<&Value> = body["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|ticket| &ticket["id"])
+            .collect();
+        assert_eq!(ids, [&json!(3), &json!(4), &json!(5), &json!(6)]);
+
+        let page: Page<Ticket> = client
+            .get(format!("{}/tickets", server_url))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(page.items.len(), 10);
+        assert_eq!(
+            (page.total, page.offset, page.limit),
+            (10, 0, DEFAULT_PAGE_LIMIT)
+        );
+    }
+    #[tokio::test]
+    async fn test_metrics() {
+        let server_url = setup_server().await;
+        let client = reqwest::Client::new();
+        for id in 0..3 {
+            create_ticket(&client, &server_url, id, "todo").await;
+        }
+
+        let response = client
+            .get(format!("{}/metrics", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert!(response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/plain"));
+        let body = response.text().await.unwrap();
+        let samples: Vec<(&str, u64)> = body
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let (name, value) = line.split_once(' ').unwrap();
+                (name, value.parse().unwrap())
+            })
+            .collect();
+        assert_eq!(
+            samples,
+            vec![
+                ("ticket_inserts_total", 3),
+                ("ticket_updates_total", 0),
+                ("ticket_deletes_total", 0),
+                ("tickets", 3),
+            ]
+        );
+
+        let json: Value = client
+            .get(format!("{}/metrics?format=json", server_url))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(json["inserts_total"], 3);
+    }
+    #[tokio::test]
+    async fn test_unknown_path() {
+        let server_url = setup_server().await;
+        let response = reqwest::get(format!("{}/no/such/route", server_url))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(
+            body,
+            json!({"error": {"code": "NOT_FOUND", "message": "No route for /no/such/route"}})
+        );
+    }
+    #[tokio::test]
+    async fn test_patch_ticket_errors() {
+        let server_url = setup_server().await;
+        let client = reqwest::Client::new();
+        create_ticket(&client, &server_url, 42, "todo").await;
+
+        let missing = TicketPatch::new(7.into(), None, None, Some("done".try_into().unwrap()));
+        let response = client
+            .patch(format!("{}/ticket", server_url))
+            .json(&missing)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let mismatched = TicketPatch::new(7.into(), None, None, Some("done".try_into().unwrap()));
+        let response = client
+            .patch(format!("{}/tickets/42", server_url))
+            .json(&mismatched)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+    #[tokio::test]
+    async fn test_health() {
+        let server_url = setup_server().await;
+        let response = reqwest::get(format!("{}/health", server_url))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["reasons"], json!([]));
+    }
+    #[tokio::test]
+    async fn test_health_by_store_size() {
+        let store = TicketStore::new();
+        let thresholds = HealthThresholds {
+            degraded_tickets: Some(1),
+            unhealthy_tickets: Some(3),
+            ..HealthThresholds::default()
+        };
+        let server_url = serve(build_app(store.clone(), thresholds)).await;
+        let insert = |id: u64| store.insert(sample_ticket(id, "todo")).unwrap();
+
+        insert(1);
+        insert(2);
+        let response = reqwest::get(format!("{}/health", server_url))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["status"], "degraded");
+        assert_eq!(body["tickets"], 2);
+
+        insert(3);
+        insert(4);
+        let response = reqwest::get(format!("{}/health", server_url))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["status"], "unhealthy");
+    }
+    #[tokio::test]
+    async fn test_health_degraded_by_lagging_subscriber() {
+        let store = TicketStore::new();
+        let thresholds = HealthThresholds {
+            degraded_event_backlog: Some(2),
+            ..HealthThresholds::default()
+        };
+        let server_url = serve(build_app(store.clone(), thresholds)).await;
+        // Never reads, so every event stays buffered.
+        let _stalled = store.subscribe();
+        for id in 0..3 {
+            store.insert(sample_ticket(id, "todo")).unwrap();
+        }
+
+        let response = reqwest::get(format!("{}/health", server_url))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["status"], "degraded");
+        assert_eq!(body["event_backlog"], 3);
+        assert_eq!(body["reasons"].as_array().unwrap().len(), 1);
+    }
+    #[tokio::test]
+    async fn test_get_ticket_fields() {
+        let server_url = setup_server().await;
+        let client = reqwest::Client::new();
+        let ticket = create_ticket(&client, &server_url, 42, "todo").await;
+
+        let response = client
+            .get(format!("{}/ticket?fields=id,status", server_url))
+            .json(&ticket.id)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body, json!({"id": 42, "status": "ToDo"}));
+
+        let response = client
+            .get(format!("{}/ticket?fields=id,owner", server_url))
+            .json(&ticket.id)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(
+            body,
+            json!({"error": {"code": "BAD_REQUEST", "message": "Unknown ticket field `owner`"}})
+        );
+    }
+    #[tokio::test]
+    async fn test_lock_ticket() {
+        let server_url = setup_server().await;
+        let client = reqwest::Client::new();
+        let ticket = create_ticket(&client, &server_url, 42, "todo").await;
+
+        let response = client
+            .post(format!("{}/tickets/42/lock", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = response.json().await.unwrap();
+        let token = body["token"].as_str().unwrap().to_string();
+
+        let patch = TicketPatch::new(ticket.id, None, None, Some("done".try_into().unwrap()));
+        let response = client
+            .patch(format!("{}/tickets/42", server_url))
+            .header(LOCK_TOKEN, &token)
+            .json(&patch)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Someone else, without the token.
+        let other = reqwest::Client::new();
+        let response = other
+            .post(format!("{}/tickets/42/lock", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::LOCKED);
+        let patch = TicketPatch::new(ticket.id, None, None, Some("todo".try_into().unwrap()));
+        for request in [
+            other.patch(format!("{}/tickets/42", server_url)),
+            other.patch(format!("{}/ticket", server_url)),
+            other
+                .patch(format!("{}/ticket", server_url))
+                .header(LOCK_TOKEN, "not-the-token"),
+        ] {
+            let response = request.json(&patch).send().await.unwrap();
+            assert_eq!(response.status(), StatusCode::LOCKED);
+        }
+
+        let response = client
+            .post(format!("{}/tickets/7/lock", server_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
 }
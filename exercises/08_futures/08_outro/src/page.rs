@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+// One window of a listing, along with where it sits in the whole thing.
+// Serializes as `{"items": [...], "total": N, "offset": O, "limit": L}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    // Size of the whole listing, not just of this page.
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl<T> Page<T> {
+    // Up to `limit` items of `all`, starting at `offset`. An `offset` past
+    // the end gives an empty page that still reports the total.
+    pub fn window(all: Vec<T>, offset: usize, limit: usize) -> Self {
+        let total = all.len();
+        let items = all.into_iter().skip(offset).take(limit).collect();
+        Page {
+            items,
+            total,
+            offset,
+            limit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window() {
+        let page = Page::window((0..10).collect(), 8, 5);
+        assert_eq!(page.items, vec![8, 9]);
+        assert_eq!((page.total, page.offset, page.limit), (10, 8, 5));
+
+        let past_the_end = Page::window(vec![1, 2, 3], 5, 5);
+        assert!(past_the_end.items.is_empty());
+        assert_eq!(past_the_end.total, 3);
+    }
+}
@@ -1,14 +1,22 @@
 use std::fmt::Display;
+use std::time::{Duration, SystemTime};
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+// Field names are part of the API: renaming a field renames it on the wire,
+// which `test_ticket_json_field_names` catches.
 #[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct Ticket {
     pub title: TicketTitle,
     pub description: TicketDescription,
     pub id: TicketId,
     pub status: TicketStatus,
+    // Tickets sent without timestamps are stamped on arrival.
+    #[serde(default = "SystemTime::now")]
+    pub created_at: SystemTime,
+    #[serde(default = "SystemTime::now")]
+    pub updated_at: SystemTime,
 }
 
 #[derive(Debug, PartialEq, PartialOrd, Error)]
@@ -24,13 +32,20 @@ impl Ticket {
         description: TicketDescription,
         status: TicketStatus,
     ) -> Self {
+        let now = SystemTime::now();
         Self {
             id,
             title,
             description,
             status,
+            created_at: now,
+            updated_at: now,
         }
     }
+    // How long ago the ticket was created, or zero if `now` is before that.
+    pub fn age(&self, now: SystemTime) -> Duration {
+        now.duration_since(self.created_at).unwrap_or_default()
+    }
     pub fn update(&mut self, patch: TicketPatch) -> Result<(), TicketUpdateError> {
         if self.id != patch.id {
             return Err(TicketUpdateError {
@@ -38,20 +53,50 @@ impl Ticket {
                 patch_id: patch.id,
             });
         }
+        // Each changed field is logged as `old -> new`; unchanged ones are left out.
+        let mut title_change = None;
         if let Some(title) = patch.title {
+            if title != self.title {
+                title_change = Some(format!("{} -> {}", self.title.as_str(), title.as_str()));
+            }
             self.title = title;
         }
+        let mut description_change = None;
         if let Some(description) = patch.description {
+            if description != self.description {
+                description_change = Some(format!(
+                    "{} -> {}",
+                    self.description.as_str(),
+                    description.as_str()
+                ));
+            }
             self.description = description;
         }
+        let mut status_change = None;
         if let Some(status) = patch.status {
+            if status != self.status {
+                status_change = Some(format!("{} -> {}", self.status, status));
+            }
             self.status = status;
         };
+        if title_change.is_some() || description_change.is_some() || status_change.is_some() {
+            tracing::info!(
+                id = %self.id,
+                title = title_change,
+                description = description_change,
+                status = status_change,
+                "ticket updated"
+            );
+        }
+        self.updated_at = SystemTime::now();
         Ok(())
     }
 }
 
+// Unknown fields are rejected, so a typo like `titel` fails loudly instead
+// of turning into a patch that changes nothing.
 #[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct TicketPatch {
     pub id: TicketId,
     pub title: Option<TicketTitle>,
@@ -72,6 +117,17 @@ impl TicketPatch {
             status,
         }
     }
+    // True if applying this to `ticket` wouldn't change any field.
+    pub fn changes_nothing(&self, ticket: &Ticket) -> bool {
+        self.title
+            .as_ref()
+            .is_none_or(|title| *title == ticket.title)
+            && self
+                .description
+                .as_ref()
+                .is_none_or(|description| *description == ticket.description)
+            && self.status.is_none_or(|status| status == ticket.status)
+    }
 }
 
 #[derive(Debug, Clone, Error, PartialEq, Serialize, Deserialize)]
@@ -88,18 +144,100 @@ pub enum TicketParseError {
     TitleEmptyError(String),
     #[error("`{0}` is empty, for a description, min bytes is 1 byte")]
     DebugEmptyError(String),
+    #[error("`{0}` is not a valid status byte, should be one of these, 0 (todo), 1 (inprogress), 2 (done)")]
+    StatusByteParseError(u8),
+    #[error("`{0}` is not a valid custom status, use 1 to 24 lowercase letters, digits, `-` or `_`, other than a built-in status")]
+    CustomStatusParseError(String),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
+// Custom statuses only mean something to a store that registered them,
+// see `TicketStore::with_custom_statuses`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum TicketStatus {
     ToDo,
     InProgress,
     Done,
+    Custom(CustomStatus),
+}
+
+impl TicketStatus {
+    // The bytes are a stable wire encoding, don't renumber them. Custom
+    // statuses have no byte of their own.
+    pub fn as_u8(self) -> Option<u8> {
+        match self {
+            Self::ToDo => Some(0),
+            Self::InProgress => Some(1),
+            Self::Done => Some(2),
+            Self::Custom(_) => None,
+        }
+    }
+    pub fn from_u8(value: u8) -> Result<Self, TicketParseError> {
+        match value {
+            0 => Ok(Self::ToDo),
+            1 => Ok(Self::InProgress),
+            2 => Ok(Self::Done),
+            _ => Err(TicketParseError::StatusByteParseError(value)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct TicketId(u64);
 
+// Kept inline rather than in a `String` so `TicketStatus` stays `Copy`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CustomStatus {
+    len: u8,
+    bytes: [u8; CustomStatus::MAX_LEN],
+}
+impl CustomStatus {
+    pub const MAX_LEN: usize = 24;
+
+    pub fn new(name: &str) -> Result<Self, TicketParseError> {
+        let valid = !name.is_empty()
+            && name.len() <= Self::MAX_LEN
+            && name
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+            && TicketStatus::try_from(name).is_err();
+        if !valid {
+            return Err(TicketParseError::CustomStatusParseError(name.to_string()));
+        }
+        let mut bytes = [0; Self::MAX_LEN];
+        bytes[..name.len()].copy_from_slice(name.as_bytes());
+        Ok(Self {
+            len: name.len() as u8,
+            bytes,
+        })
+    }
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..usize::from(self.len)]).expect("validated as ASCII")
+    }
+}
+impl TryFrom<String> for CustomStatus {
+    type Error = TicketParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::new(&value)
+    }
+}
+impl From<CustomStatus> for String {
+    fn from(value: CustomStatus) -> Self {
+        value.as_str().to_string()
+    }
+}
+impl std::fmt::Debug for CustomStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CustomStatus").field(&self.as_str()).finish()
+    }
+}
+impl Display for CustomStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct TicketTitle(String);
 
@@ -130,44 +268,119 @@ impl TryFrom<String> for TicketStatus {
         }
     }
 }
+// Titles and descriptions only differ in their length bounds and in the
+// errors they report, so the validation itself lives here.
+pub(crate) trait BoundedString: Sized {
+    const MIN_LEN: usize;
+    const MAX_LEN: usize;
+
+    fn too_short(value: String) -> TicketParseError;
+    fn too_long(value: String) -> TicketParseError;
+    fn new_unchecked(value: String) -> Self;
+
+    fn parse(value: String) -> Result<Self, TicketParseError> {
+        if value.len() < Self::MIN_LEN {
+            return Err(Self::too_short(value));
+        }
+        if value.len() > Self::MAX_LEN {
+            return Err(Self::too_long(value));
+        }
+        Ok(Self::new_unchecked(value))
+    }
+}
+
+impl BoundedString for TicketTitle {
+    const MIN_LEN: usize = 1;
+    const MAX_LEN: usize = 50;
+
+    fn too_short(value: String) -> TicketParseError {
+        TicketParseError::TitleEmptyError(value)
+    }
+    fn too_long(value: String) -> TicketParseError {
+        TicketParseError::TitleTooLongError(value)
+    }
+    fn new_unchecked(value: String) -> Self {
+        Self(value)
+    }
+}
+impl BoundedString for TicketDescription {
+    const MIN_LEN: usize = 1;
+    const MAX_LEN: usize = 500;
+
+    fn too_short(value: String) -> TicketParseError {
+        TicketParseError::DebugEmptyError(value)
+    }
+    fn too_long(value: String) -> TicketParseError {
+        TicketParseError::DebugTooLongError(value)
+    }
+    fn new_unchecked(value: String) -> Self {
+        Self(value)
+    }
+}
+
 impl TryFrom<&str> for TicketTitle {
     type Error = TicketParseError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        if value.is_empty() {
-            return Err(TicketParseError::TitleEmptyError(value.to_string()));
-        }
-        if value.len() > 50 {
-            return Err(TicketParseError::TitleTooLongError(value.to_string()));
-        }
-        Ok(Self(value.to_string()))
+        Self::parse(value.to_string())
     }
 }
 impl TryFrom<String> for TicketTitle {
     type Error = TicketParseError;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        Self::try_from(value.as_str())
+        Self::parse(value)
     }
 }
 impl TryFrom<&str> for TicketDescription {
     type Error = TicketParseError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        if value.is_empty() {
-            return Err(TicketParseError::DebugEmptyError(value.to_string()));
-        }
-        if value.len() > 500 {
-            return Err(TicketParseError::DebugTooLongError(value.to_string()));
-        }
-        Ok(Self(value.to_string()))
+        Self::parse(value.to_string())
     }
 }
 impl TryFrom<String> for TicketDescription {
     type Error = TicketParseError;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        Self::try_from(value.as_str())
+        Self::parse(value)
+    }
+}
+impl TicketTitle {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+impl TicketDescription {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+impl Display for TicketStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Built-in statuses use the spelling `TryFrom<&str>` accepts. Custom
+        // ones are written by name, which `TicketStore::parse_status` reads
+        // back if the store registered them.
+        let status = match self {
+            TicketStatus::ToDo => "todo",
+            TicketStatus::InProgress => "inprogress",
+            TicketStatus::Done => "done",
+            TicketStatus::Custom(custom) => custom.as_str(),
+        };
+        write!(f, "{}", status)
+    }
+}
+// One line per ticket, for terminals: `#42 [todo] title: description`.
+impl Display for Ticket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "#{} [{}] {}: {}",
+            self.id,
+            self.status,
+            self.title.as_str(),
+            self.description.as_str()
+        )
     }
 }
 impl From<u64> for TicketId {
@@ -175,6 +388,11 @@ impl From<u64> for TicketId {
         Self(value)
     }
 }
+impl From<TicketId> for u64 {
+    fn from(id: TicketId) -> Self {
+        id.0
+    }
+}
 impl Display for TicketId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -199,6 +417,46 @@ mod tests {
         );
     }
     #[test]
+    fn test_custom_status() {
+        let blocked = CustomStatus::new("blocked-on-review").unwrap();
+        assert_eq!(blocked.as_str(), "blocked-on-review");
+        assert_eq!(
+            TicketStatus::Custom(blocked).to_string(),
+            "blocked-on-review"
+        );
+        assert_eq!(TicketStatus::Custom(blocked).as_u8(), None);
+
+        let max = "x".repeat(CustomStatus::MAX_LEN);
+        assert!(CustomStatus::new(&max).is_ok());
+        for invalid in ["", "Blocked", "on hold", "done", &format!("{}x", max)] {
+            assert_eq!(
+                CustomStatus::new(invalid).unwrap_err(),
+                TicketParseError::CustomStatusParseError(invalid.to_string())
+            );
+        }
+
+        let serialized = serde_json::to_string(&TicketStatus::Custom(blocked)).unwrap();
+        assert_eq!(serialized, r#"{"Custom":"blocked-on-review"}"#);
+        let deserialized: TicketStatus = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, TicketStatus::Custom(blocked));
+        assert!(serde_json::from_str::<TicketStatus>(r#"{"Custom":"Nope"}"#).is_err());
+    }
+    #[test]
+    fn test_ticket_status_u8() {
+        for (status, byte) in [
+            (TicketStatus::ToDo, 0),
+            (TicketStatus::InProgress, 1),
+            (TicketStatus::Done, 2),
+        ] {
+            assert_eq!(status.as_u8(), Some(byte));
+            assert_eq!(TicketStatus::from_u8(byte).unwrap(), status);
+        }
+        assert_eq!(
+            TicketStatus::from_u8(3).unwrap_err(),
+            TicketParseError::StatusByteParseError(3)
+        );
+    }
+    #[test]
     fn test_ticket_title() {
         assert_eq!(
             TicketTitle::try_from("valid title").unwrap(),
@@ -229,6 +487,25 @@ mod tests {
         );
     }
     #[test]
+    fn test_bounded_string_limits() {
+        assert!(TicketTitle::try_from("a").is_ok());
+        assert!(TicketTitle::try_from("a".repeat(50)).is_ok());
+        assert_eq!(
+            TicketTitle::try_from("a".repeat(51)).unwrap_err(),
+            TicketParseError::TitleTooLongError("a".repeat(51))
+        );
+        assert!(TicketDescription::try_from("a").is_ok());
+        assert!(TicketDescription::try_from("a".repeat(500)).is_ok());
+        assert_eq!(
+            TicketDescription::try_from("a".repeat(501)).unwrap_err(),
+            TicketParseError::DebugTooLongError("a".repeat(501))
+        );
+        assert_eq!(
+            TicketDescription::try_from(String::new()).unwrap_err(),
+            TicketParseError::DebugEmptyError(String::new())
+        );
+    }
+    #[test]
     fn test_ticket_id() {
         assert_eq!(TicketId::from(1), TicketId(1));
         assert_eq!(TicketId::from(0), TicketId(0));
@@ -236,12 +513,27 @@ mod tests {
         assert_eq!(TicketId::try_from(42).unwrap(), TicketId(42));
     }
     #[test]
+    fn test_ticket_display() {
+        let ticket = Ticket::new(
+            TicketId(7),
+            TicketTitle("Test Ticket".to_string()),
+            TicketDescription("This is a test ticket".to_string()),
+            TicketStatus::InProgress,
+        );
+        assert_eq!(
+            ticket.to_string(),
+            "#7 [inprogress] Test Ticket: This is a test ticket"
+        );
+    }
+    #[test]
     fn test_ticket_serde() {
         let ticket = Ticket {
             title: TicketTitle("Test Ticket".to_string()),
             description: TicketDescription("This is a test ticket".to_string()),
             id: TicketId(1),
             status: TicketStatus::ToDo,
+            created_at: SystemTime::UNIX_EPOCH,
+            updated_at: SystemTime::UNIX_EPOCH,
         };
         let serialized = serde_json::to_string(&ticket).unwrap();
         let deserialized: Ticket = serde_json::from_str(&serialized).unwrap();
@@ -277,6 +569,36 @@ mod tests {
         eprintln!("Serialized: {}", serialized);
     }
     #[test]
+    fn test_ticket_patch_rejects_unknown_fields() {
+        let json_str = r#"{"id":1,"titel":"Updated Title"}"#;
+        let err = serde_json::from_str::<TicketPatch>(json_str).unwrap_err();
+        assert!(err.to_string().contains("unknown field `titel`"), "{}", err);
+    }
+    #[test]
+    fn test_ticket_json_field_names() {
+        let ticket = Ticket::new(
+            TicketId(1),
+            TicketTitle("title".to_string()),
+            TicketDescription("description".to_string()),
+            TicketStatus::ToDo,
+        );
+        let serde_json::Value::Object(fields) = serde_json::to_value(&ticket).unwrap() else {
+            panic!("a ticket serializes to an object");
+        };
+        let names: Vec<&str> = fields.keys().map(String::as_str).collect();
+        assert_eq!(
+            names,
+            [
+                "created_at",
+                "description",
+                "id",
+                "status",
+                "title",
+                "updated_at"
+            ]
+        );
+    }
+    #[test]
     fn test_ticket_patch_serde_no_description() {
         let json_str = r#"{"id":1,"title":"Updated Title","status":"InProgress"}"#;
         let deserialized: TicketPatch = serde_json::from_str(json_str).unwrap();
@@ -296,6 +618,8 @@ mod tests {
             description: TicketDescription("Original Description".to_string()),
             id: TicketId(1),
             status: TicketStatus::ToDo,
+            created_at: SystemTime::UNIX_EPOCH,
+            updated_at: SystemTime::UNIX_EPOCH,
         };
         let patch = TicketPatch {
             id: TicketId(1),
@@ -306,6 +630,81 @@ mod tests {
         ticket.update(patch).unwrap();
         assert_eq!(ticket.title, TicketTitle("Updated Title".to_string()));
         assert_eq!(ticket.status, TicketStatus::InProgress);
+        assert_eq!(ticket.created_at, SystemTime::UNIX_EPOCH);
+        assert!(ticket.updated_at > SystemTime::UNIX_EPOCH);
+    }
+    #[test]
+    fn test_ticket_age() {
+        let mut ticket = Ticket::new(
+            TicketId(1),
+            TicketTitle("Title".to_string()),
+            TicketDescription("Description".to_string()),
+            TicketStatus::ToDo,
+        );
+        ticket.created_at = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(160);
+        assert_eq!(ticket.age(now), Duration::from_secs(60));
+        assert_eq!(ticket.age(SystemTime::UNIX_EPOCH), Duration::ZERO);
+    }
+    #[test]
+    #[tracing_test::traced_test]
+    fn test_update_ticket_logs_changes() {
+        let mut ticket = Ticket::new(
+            TicketId(1),
+            TicketTitle("Original Title".to_string()),
+            TicketDescription("Original Description".to_string()),
+            TicketStatus::ToDo,
+        );
+        let patch = TicketPatch {
+            id: TicketId(1),
+            title: Some(TicketTitle("Updated Title".to_string())),
+            description: Some(TicketDescription("Original Description".to_string())),
+            status: Some(TicketStatus::Done),
+        };
+        ticket.update(patch).unwrap();
+        let mismatched = TicketPatch {
+            id: TicketId(2),
+            title: None,
+            description: None,
+            status: Some(TicketStatus::ToDo),
+        };
+        ticket.update(mismatched).unwrap_err();
+        // Neither changes anything, so neither is logged.
+        let empty = TicketPatch {
+            id: TicketId(1),
+            title: None,
+            description: None,
+            status: None,
+        };
+        ticket.update(empty).unwrap();
+        let same = TicketPatch {
+            id: TicketId(1),
+            title: None,
+            description: None,
+            status: Some(TicketStatus::Done),
+        };
+        ticket.update(same).unwrap();
+
+        logs_assert(|lines: &[&str]| {
+            let updates: Vec<&str> = lines
+                .iter()
+                .copied()
+                .filter(|line| line.contains("ticket updated"))
+                .collect();
+            match updates[..] {
+                [line]
+                    if line.contains("id=1")
+                        && line.contains(r#"title="Original Title -> Updated Title""#)
+                        && !line.contains("description=")
+                        && line.contains(r#"status="todo -> done""#) =>
+                {
+                    Ok(())
+                }
+                _ => Err(format!(
+                    "expected one update with the changes, got {updates:?}"
+                )),
+            }
+        });
     }
     #[test]
     fn test_update_ticket_id_mismatch() {
@@ -314,6 +713,8 @@ mod tests {
             description: TicketDescription("Original Description".to_string()),
             id: TicketId(1),
             status: TicketStatus::ToDo,
+            created_at: SystemTime::UNIX_EPOCH,
+            updated_at: SystemTime::UNIX_EPOCH,
         };
         let patch = TicketPatch {
             id: TicketId(2),
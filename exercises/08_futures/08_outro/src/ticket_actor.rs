@@ -0,0 +1,355 @@
+use futures_util::future::try_join_all;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
+
+use crate::ticket::{Ticket, TicketId, TicketPatch};
+use crate::ticket_store::{InsertError, PatchError, PatchOutcome, TicketStore};
+
+// How many requests can queue up before callers have to wait.
+const MAILBOX_CAPACITY: usize = 64;
+
+// A cloneable handle to tickets owned by background actor tasks. With more
+// than one actor, each owns its own `TicketStore` holding the tickets whose
+// id modulo the number of actors is its index, and requests go to the
+// actor owning the ticket.
+#[derive(Debug, Clone)]
+pub struct TicketStoreHandle {
+    // One per actor, indexed by shard.
+    senders: Arc<[mpsc::Sender<Request>]>,
+    cancel: CancellationToken,
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum RequestError {
+    // The request may or may not have been applied, see
+    // `TicketStoreHandle::with_cancellation`.
+    #[error("The request was cancelled")]
+    Cancelled,
+    #[error("The store actor is no longer running")]
+    ActorGone,
+    #[error(transparent)]
+    Insert(#[from] InsertError),
+    #[error(transparent)]
+    Patch(#[from] PatchError),
+}
+
+struct Request {
+    command: Command,
+    cancel: CancellationToken,
+}
+
+enum Command {
+    Insert {
+        ticket: Ticket,
+        reply: oneshot::Sender<Result<(), InsertError>>,
+    },
+    Get {
+        id: TicketId,
+        reply: oneshot::Sender<Option<Arc<Ticket>>>,
+    },
+    Patch {
+        id: TicketId,
+        patch: TicketPatch,
+        reply: oneshot::Sender<Result<PatchOutcome, PatchError>>,
+    },
+    PatchMany {
+        patches: Vec<TicketPatch>,
+        reply: oneshot::Sender<Vec<Result<PatchOutcome, PatchError>>>,
+    },
+    List {
+        reply: oneshot::Sender<Vec<Arc<Ticket>>>,
+    },
+}
+
+impl TicketStoreHandle {
+    // Spawns `workers` actors, each with an empty store, so it must be
+    // called from within a Tokio runtime.
+    //
+    // Panics if `workers` is zero.
+    pub fn new(workers: usize) -> Self {
+        assert!(workers > 0, "tickets need at least one actor to own them");
+        Self::spawn_shards((0..workers).map(|_| TicketStore::new()))
+    }
+    // A single actor owning `store`.
+    pub fn spawn(store: TicketStore) -> Self {
+        Self::spawn_shards([store])
+    }
+    fn spawn_shards(stores: impl IntoIterator<Item = TicketStore>) -> Self {
+        let senders = stores
+            .into_iter()
+            .map(|store| {
+                let (sender, receiver) = mpsc::channel(MAILBOX_CAPACITY);
+                tokio::spawn(run(store, receiver));
+                sender
+            })
+            .collect();
+        Self {
+            senders,
+            cancel: CancellationToken::new(),
+        }
+    }
+    // Requests made through the returned handle give up as soon as `cancel`
+    // fires. A request the actor hasn't picked up yet is then skipped, so it
+    // never touches the store, but one it had already started on still goes
+    // through: `Cancelled` means the outcome is unknown, not that nothing
+    // changed.
+    pub fn with_cancellation(&self, cancel: CancellationToken) -> Self {
+        Self {
+            senders: self.senders.clone(),
+            cancel,
+        }
+    }
+    fn shard_of(&self, id: TicketId) -> usize {
+        (u64::from(id) % self.senders.len() as u64) as usize
+    }
+    pub async fn insert(&self, ticket: Ticket) -> Result<(), RequestError> {
+        let shard = self.shard_of(ticket.id);
+        Ok(self
+            .request(shard, |reply| Command::Insert { ticket, reply })
+            .await??)
+    }
+    pub async fn get(&self, id: TicketId) -> Result<Option<Arc<Ticket>>, RequestError> {
+        self.request(self.shard_of(id), |reply| Command::Get { id, reply })
+            .await
+    }
+    pub async fn patch(
+        &self,
+        id: TicketId,
+        patch: TicketPatch,
+    ) -> Result<PatchOutcome, RequestError> {
+        Ok(self
+            .request(self.shard_of(id), |reply| Command::Patch {
+                id,
+                patch,
+                reply,
+            })
+            .await??)
+    }
+    // See `TicketStore::patch_many`. Each actor applies its share of the
+    // batch in one go, but with several actors another request can see one
+    // share applied before the others.
+    pub async fn patch_many(
+        &self,
+        patches: Vec<TicketPatch>,
+    ) -> Result<Vec<Result<PatchOutcome, PatchError>>, RequestError> {
+        // Remember where each patch was, to put the results back in order.
+        let mut shares: Vec<Vec<(usize, TicketPatch)>> = vec![Vec::new(); self.senders.len()];
+        for (position, patch) in patches.into_iter().enumerate() {
+            shares[self.shard_of(patch.id)].push((position, patch));
+        }
+        let requests = shares
+            .into_iter()
+            .enumerate()
+            .filter(|(_, share)| !share.is_empty())
+            .map(|(shard, share)| async move {
+                let (positions, patches): (Vec<_>, Vec<_>) = share.into_iter().unzip();
+                let results = self
+                    .request(shard, |reply| Command::PatchMany { patches, reply })
+                    .await?;
+                Ok::<_, RequestError>(positions.into_iter().zip(results))
+            });
+        let mut results: Vec<_> = try_join_all(requests)
+            .await?
+            .into_iter()
+            .flatten()
+            .collect();
+        results.sort_by_key(|(position, _)| *position);
+        Ok(results.into_iter().map(|(_, result)| result).collect())
+    }
+    // Every ticket in id order, gathered from all the actors.
+    pub async fn list(&self) -> Result<Vec<Arc<Ticket>>, RequestError> {
+        let requests = (0..self.senders.len())
+            .map(|shard| self.request(shard, |reply| Command::List { reply }));
+        let mut tickets: Vec<_> = try_join_all(requests)
+            .await?
+            .into_iter()
+            .flatten()
+            .collect();
+        tickets.sort_by_key(|ticket| ticket.id);
+        Ok(tickets)
+    }
+    async fn request<T>(
+        &self,
+        shard: usize,
+        command: impl FnOnce(oneshot::Sender<T>) -> Command,
+    ) -> Result<T, RequestError> {
+        let (reply, response) = oneshot::channel();
+        let request = Request {
+            command: command(reply),
+            cancel: self.cancel.clone(),
+        };
+        let sender = &self.senders[shard];
+        tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => return Err(RequestError::Cancelled),
+            sent = sender.send(request) => sent.map_err(|_| RequestError::ActorGone)?,
+        }
+        // If `cancel` fires after the actor has checked it, the command is
+        // still applied, we just stop waiting to hear about it.
+        tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => Err(RequestError::Cancelled),
+            response = response => response.map_err(|_| RequestError::ActorGone),
+        }
+    }
+}
+
+async fn run(store: TicketStore, mut receiver: mpsc::Receiver<Request>) {
+    while let Some(Request { command, cancel }) = receiver.recv().await {
+        // The caller has already given up on this request.
+        if cancel.is_cancelled() {
+            continue;
+        }
+        // Sending only fails if the caller is gone, and then nobody cares.
+        match command {
+            Command::Insert { ticket, reply } => {
+                let _ = reply.send(store.insert(ticket));
+            }
+            Command::Get { id, reply } => {
+                let _ = reply.send(store.get(id));
+            }
+            Command::Patch { id, patch, reply } => {
+                let _ = reply.send(store.patch(id, patch));
+            }
+            Command::PatchMany { patches, reply } => {
+                let _ = reply.send(store.patch_many(patches));
+            }
+            Command::List { reply } => {
+                let _ = reply.send(store.list());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ticket::TicketStatus;
+
+    fn get_ticket() -> Ticket {
+        Ticket::new(
+            42.into(),
+            "this is a title".try_into().unwrap(),
+            "this is a description".try_into().unwrap(),
+            "todo".try_into().unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_insert_get_patch() {
+        let handle = TicketStoreHandle::new(1);
+        let ticket = get_ticket();
+        handle.insert(ticket.clone()).await.unwrap();
+        assert_eq!(
+            handle.get(ticket.id).await.unwrap().as_deref(),
+            Some(&ticket)
+        );
+
+        let patch = TicketPatch::new(ticket.id, None, None, Some(TicketStatus::Done));
+        handle.patch(ticket.id, patch).await.unwrap();
+        let patched = handle.get(ticket.id).await.unwrap().unwrap();
+        assert_eq!(patched.status, TicketStatus::Done);
+    }
+    #[tokio::test]
+    async fn test_patch_many() {
+        let handle = TicketStoreHandle::new(1);
+        let ticket = get_ticket();
+        handle.insert(ticket.clone()).await.unwrap();
+
+        let results = handle
+            .patch_many(vec![
+                TicketPatch::new(7.into(), None, None, Some(TicketStatus::Done)),
+                TicketPatch::new(ticket.id, None, None, Some(TicketStatus::Done)),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(
+            results,
+            vec![
+                Err(PatchError::NotFound(7.into())),
+                Ok(PatchOutcome::Changed)
+            ]
+        );
+        let patched = handle.get(ticket.id).await.unwrap().unwrap();
+        assert_eq!(patched.status, TicketStatus::Done);
+    }
+    #[tokio::test]
+    async fn test_cancelled_before_sending() {
+        let handle = TicketStoreHandle::new(1);
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let ticket = get_ticket();
+        let result = handle
+            .with_cancellation(cancel)
+            .insert(ticket.clone())
+            .await;
+        assert_eq!(result, Err(RequestError::Cancelled));
+        assert_eq!(handle.get(ticket.id).await.unwrap(), None);
+    }
+    #[tokio::test]
+    async fn test_cancelled_in_flight() {
+        // Don't start the actor yet, so the request sits in the mailbox.
+        let (sender, receiver) = mpsc::channel(MAILBOX_CAPACITY);
+        let handle = TicketStoreHandle {
+            senders: Arc::new([sender]),
+            cancel: CancellationToken::new(),
+        };
+        let cancel = CancellationToken::new();
+        let cancellable = handle.with_cancellation(cancel.clone());
+        let ticket = get_ticket();
+        let in_flight = tokio::spawn(async move { cancellable.insert(get_ticket()).await });
+        while handle.senders[0].capacity() == MAILBOX_CAPACITY {
+            tokio::task::yield_now().await;
+        }
+
+        cancel.cancel();
+        assert_eq!(in_flight.await.unwrap(), Err(RequestError::Cancelled));
+
+        let store = TicketStore::new();
+        tokio::spawn(run(store.clone(), receiver));
+        assert_eq!(handle.get(ticket.id).await.unwrap(), None);
+        assert_eq!(store.get(ticket.id), None);
+    }
+    #[tokio::test]
+    async fn test_sharded_list_and_patch_many() {
+        let handle = TicketStoreHandle::new(3);
+        for id in [7, 2, 9, 4, 0, 5] {
+            let mut ticket = get_ticket();
+            ticket.id = id.into();
+            handle.insert(ticket).await.unwrap();
+        }
+        assert_eq!(
+            handle.get(7.into()).await.unwrap().map(|ticket| ticket.id),
+            Some(7.into())
+        );
+        let ids: Vec<TicketId> = handle
+            .list()
+            .await
+            .unwrap()
+            .iter()
+            .map(|ticket| ticket.id)
+            .collect();
+        let expected: Vec<TicketId> = [0, 2, 4, 5, 7, 9].map(TicketId::from).to_vec();
+        assert_eq!(ids, expected);
+
+        let done = |id: u64| TicketPatch::new(id.into(), None, None, Some(TicketStatus::Done));
+        let results = handle
+            .patch_many(vec![done(9), done(1), done(4), done(0)])
+            .await
+            .unwrap();
+        assert_eq!(
+            results,
+            vec![
+                Ok(PatchOutcome::Changed),
+                Err(PatchError::NotFound(1.into())),
+                Ok(PatchOutcome::Changed),
+                Ok(PatchOutcome::Changed),
+            ]
+        );
+        let patched = handle.get(4.into()).await.unwrap().unwrap();
+        assert_eq!(patched.status, TicketStatus::Done);
+    }
+}
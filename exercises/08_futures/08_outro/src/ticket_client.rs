@@ -0,0 +1,329 @@
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+use std::time::Duration;
+use thiserror::Error;
+
+use crate::page::Page;
+use crate::ticket::{Ticket, TicketId, TicketStatus};
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+// Talks to the REST API served by this crate's binary.
+//
+// Idempotent requests (`get`, `list`, `delete_by_status`) are retried when
+// the connection is refused or the server answers with a 5xx, waiting
+// twice as long before each new attempt. `create` is only retried once an
+// idempotency key is set, since otherwise a retry could create the ticket
+// twice. This crate's server ignores the key: retrying is only safe there
+// because tickets carry their own id, so a repeated `create` stores the same
+// ticket again instead of adding a second one.
+#[derive(Debug, Clone)]
+pub struct TicketApiClient {
+    base_url: String,
+    http: reqwest::Client,
+    max_attempts: u32,
+    initial_backoff: Duration,
+    idempotency_key: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("The server answered {status}: {body}")]
+    Status { status: StatusCode, body: String },
+}
+
+#[derive(Deserialize)]
+struct Created {
+    id: TicketId,
+}
+
+#[derive(Deserialize)]
+struct Deleted {
+    deleted: usize,
+}
+
+impl TicketApiClient {
+    // `base_url` is everything before the route, e.g. `http://127.0.0.1:3000`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            http: reqwest::Client::new(),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            idempotency_key: None,
+        }
+    }
+    // Counts the first try, so `1` turns retries off.
+    //
+    // Panics if `attempts` is zero.
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        assert!(attempts > 0, "a request needs at least one attempt");
+        self.max_attempts = attempts;
+        self
+    }
+    // How long to wait before the first retry; each later one waits twice
+    // as long as the one before.
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+    // Sent as an `Idempotency-Key` header on `create`, which then retries
+    // like the other requests.
+    pub fn with_idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+    pub async fn create(&self, ticket: &Ticket) -> Result<TicketId, ClientError> {
+        let url = self.url("/ticket");
+        let retry = self.idempotency_key.is_some();
+        let response = self
+            .send(retry, || {
+                let request = self.http.post(&url).json(ticket);
+                match &self.idempotency_key {
+                    Some(key) => request.header("Idempotency-Key", key),
+                    None => request,
+                }
+            })
+            .await?;
+        Ok(response.json::<Created>().await?.id)
+    }
+    pub async fn get(&self, id: TicketId) -> Result<Ticket, ClientError> {
+        let url = self.url("/ticket");
+        let response = self.send(true, || self.http.get(&url).json(&id)).await?;
+        Ok(response.json().await?)
+    }
+    // The first page, as big as the server makes it by default.
+    pub async fn list(&self) -> Result<Page<Ticket>, ClientError> {
+        let url = self.url("/tickets");
+        let response = self.send(true, || self.http.get(&url)).await?;
+        Ok(response.json().await?)
+    }
+    pub async fn list_page(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Page<Ticket>, ClientError> {
+        let url = self.url("/tickets");
+        let response = self
+            .send(true, || {
+                self.http
+                    .get(&url)
+                    .query(&[("offset", offset), ("limit", limit)])
+            })
+            .await?;
+        Ok(response.json().await?)
+    }
+    // Returns how many tickets were deleted.
+    pub async fn delete_by_status(&self, status: TicketStatus) -> Result<usize, ClientError> {
+        let url = self.url("/tickets");
+        let status = status.to_string();
+        let response = self
+            .send(true, || {
+                self.http.delete(&url).query(&[("status", &status)])
+            })
+            .await?;
+        Ok(response.json::<Deleted>().await?.deleted)
+    }
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url.trim_end_matches('/'), path)
+    }
+    // `request` builds a fresh request for every attempt, since sending
+    // consumes it.
+    async fn send(
+        &self,
+        retry: bool,
+        request: impl Fn() -> RequestBuilder,
+    ) -> Result<Response, ClientError> {
+        let attempts = if retry { self.max_attempts } else { 1 };
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            let outcome = request().send().await;
+            let retryable = match &outcome {
+                Ok(response) => response.status().is_server_error(),
+                Err(err) => err.is_connect(),
+            };
+            if !retryable || attempt >= attempts {
+                let response = outcome?;
+                let status = response.status();
+                if status.is_success() {
+                    return Ok(response);
+                }
+                let body = response.text().await.unwrap_or_default();
+                return Err(ClientError::Status { status, body });
+            }
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, Method};
+    use axum::response::IntoResponse;
+    use axum::{Json, Router};
+    use parking_lot::Mutex;
+    use serde_json::json;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    fn get_ticket() -> Ticket {
+        Ticket::new(
+            42.into(),
+            "this is a title".try_into().unwrap(),
+            "this is a description".try_into().unwrap(),
+            "todo".try_into().unwrap(),
+        )
+    }
+
+    #[derive(Default)]
+    struct Flaky {
+        failures: usize,
+        // The `Idempotency-Key` of every request received, in order.
+        seen: Mutex<Vec<Option<String>>>,
+    }
+
+    // Answers the first `failures` requests with a 503, and every later one
+    // the way the real server would.
+    fn flaky_app(failures: usize) -> (Router, Arc<Flaky>) {
+        let flaky = Arc::new(Flaky {
+            failures,
+            ..Flaky::default()
+        });
+        let app = Router::new().fallback(answer).with_state(flaky.clone());
+        (app, flaky)
+    }
+
+    async fn flaky_server(failures: usize) -> (String, Arc<Flaky>) {
+        let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .expect("Failed to bind TCP listener");
+        let used_addr = listener.local_addr().unwrap();
+        let (app, flaky) = flaky_app(failures);
+        tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service())
+                .await
+                .unwrap();
+        });
+        (format!("http://{}", used_addr), flaky)
+    }
+
+    async fn answer(
+        State(flaky): State<Arc<Flaky>>,
+        method: Method,
+        headers: HeaderMap,
+    ) -> axum::response::Response {
+        let key = headers
+            .get("Idempotency-Key")
+            .map(|key| key.to_str().unwrap().to_string());
+        let attempt = {
+            let mut seen = flaky.seen.lock();
+            seen.push(key);
+            seen.len()
+        };
+        if attempt <= flaky.failures {
+            return StatusCode::SERVICE_UNAVAILABLE.into_response();
+        }
+        match method {
+            Method::POST => Json(json!({"id": 42})).into_response(),
+            Method::DELETE => Json(json!({"deleted": 1})).into_response(),
+            _ if headers.contains_key("content-type") => Json(get_ticket()).into_response(),
+            _ => Json(Page::window(vec![get_ticket()], 0, 100)).into_response(),
+        }
+    }
+
+    fn client(url: &str) -> TicketApiClient {
+        TicketApiClient::new(url).with_initial_backoff(Duration::from_millis(1))
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_requests_retry_server_errors() {
+        let (url, flaky) = flaky_server(1).await;
+        assert_eq!(client(&url).get(42.into()).await.unwrap().id, 42.into());
+        assert_eq!(flaky.seen.lock().len(), 2);
+
+        let (url, flaky) = flaky_server(1).await;
+        assert_eq!(client(&url).list().await.unwrap().items.len(), 1);
+        assert_eq!(flaky.seen.lock().len(), 2);
+
+        let (url, flaky) = flaky_server(2).await;
+        let deleted = client(&url).delete_by_status(TicketStatus::Done).await;
+        assert_eq!(deleted.unwrap(), 1);
+        assert_eq!(flaky.seen.lock().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let (url, flaky) = flaky_server(5).await;
+        let err = client(&url).with_max_attempts(2).list().await.unwrap_err();
+        assert!(matches!(
+            err,
+            ClientError::Status {
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                ..
+            }
+        ));
+        assert_eq!(flaky.seen.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_create_only_retries_with_idempotency_key() {
+        let (url, flaky) = flaky_server(1).await;
+        let err = client(&url).create(&get_ticket()).await.unwrap_err();
+        assert!(matches!(err, ClientError::Status { .. }));
+        assert_eq!(flaky.seen.lock().len(), 1);
+
+        let (url, flaky) = flaky_server(1).await;
+        let id = client(&url)
+            .with_idempotency_key("create-42")
+            .create(&get_ticket())
+            .await
+            .unwrap();
+        assert_eq!(id, 42.into());
+        let key = Some("create-42".to_string());
+        assert_eq!(*flaky.seen.lock(), vec![key.clone(), key]);
+    }
+
+    // Time is paused so the backoff is virtual: the server is only let in
+    // while the client waits to retry, whatever the machine's speed.
+    #[tokio::test(start_paused = true)]
+    async fn test_retries_refused_connections() {
+        // A bound socket that isn't listening refuses connections, and keeps
+        // the port for the server below.
+        let socket = tokio::net::TcpSocket::new_v4().unwrap();
+        socket.bind(SocketAddr::from(([127, 0, 0, 1], 0))).unwrap();
+        let url = format!("http://{}", socket.local_addr().unwrap());
+
+        let err = client(&url).with_max_attempts(2).list().await.unwrap_err();
+        assert!(matches!(err, ClientError::Http(ref err) if err.is_connect()));
+
+        let start = tokio::time::Instant::now();
+        let request = tokio::spawn({
+            let client = client(&url).with_initial_backoff(Duration::from_millis(100));
+            async move { client.list().await }
+        });
+        // Halfway through the first backoff, start listening.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let (app, flaky) = flaky_app(0);
+        let listener = socket.listen(1024).unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let tickets = request.await.unwrap().unwrap();
+        assert_eq!(tickets.items.len(), 1);
+        assert_eq!(flaky.seen.lock().len(), 1);
+        // Only a retry after the full backoff could have reached the server.
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,115 @@
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::BuildHasher;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use crate::ticket::TicketId;
+
+// Short-lived edit locks, one per ticket. Whoever holds a live lock on a
+// ticket gets a token, and edits without that token are refused until the
+// lock expires. Clones share the same locks.
+#[derive(Debug, Clone)]
+pub struct TicketLocks {
+    ttl: Duration,
+    held: Arc<Mutex<BTreeMap<TicketId, Lock>>>,
+}
+
+#[derive(Debug)]
+struct Lock {
+    token: LockToken,
+    expires_at: Instant,
+}
+
+// Hard to guess, but not meant to stand up to a determined attacker.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct LockToken(String);
+
+impl LockToken {
+    fn generate() -> Self {
+        // Every `RandomState` is seeded differently.
+        let state = RandomState::new();
+        LockToken(format!(
+            "{:016x}{:016x}",
+            state.hash_one(0u8),
+            state.hash_one(1u8)
+        ))
+    }
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, PartialEq, Error)]
+#[error("Ticket {0} is locked by another client")]
+pub struct Locked(pub TicketId);
+
+impl TicketLocks {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            held: Arc::default(),
+        }
+    }
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+    // Fails while someone holds a lock on `id` that hasn't expired by `now`.
+    // Holding the lock already doesn't help: wait for it to expire.
+    pub fn acquire(&self, id: TicketId, now: Instant) -> Result<LockToken, Locked> {
+        let mut held = self.held.lock();
+        held.retain(|_, lock| lock.expires_at > now);
+        if held.contains_key(&id) {
+            return Err(Locked(id));
+        }
+        let token = LockToken::generate();
+        let lock = Lock {
+            token: token.clone(),
+            expires_at: now + self.ttl,
+        };
+        held.insert(id, lock);
+        Ok(token)
+    }
+    // Ok if nobody holds a live lock on `id`, or `token` is the one for it.
+    pub fn check(&self, id: TicketId, token: Option<&str>, now: Instant) -> Result<(), Locked> {
+        match self.held.lock().get(&id) {
+            Some(lock) if lock.expires_at > now && token != Some(lock.token.as_str()) => {
+                Err(Locked(id))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_lifecycle() {
+        let locks = TicketLocks::new(Duration::from_secs(30));
+        let id = TicketId::from(1);
+        let start = Instant::now();
+        assert_eq!(locks.check(id, None, start), Ok(()));
+
+        let token = locks.acquire(id, start).unwrap();
+        assert_eq!(locks.check(id, Some(token.as_str()), start), Ok(()));
+        assert_eq!(locks.check(id, None, start), Err(Locked(id)));
+        assert_eq!(locks.check(id, Some("guess"), start), Err(Locked(id)));
+        assert_eq!(locks.acquire(id, start), Err(Locked(id)));
+        assert_eq!(locks.check(TicketId::from(2), None, start), Ok(()));
+
+        let later = start + Duration::from_secs(31);
+        assert_eq!(locks.check(id, None, later), Ok(()));
+        let other = locks.acquire(id, later).unwrap();
+        assert_ne!(other, token);
+        assert_eq!(
+            locks.check(id, Some(token.as_str()), later),
+            Err(Locked(id))
+        );
+    }
+}
@@ -1,52 +1,797 @@
-use parking_lot::RwLock;
-use std::collections::BTreeMap;
+use parking_lot::{Mutex, RwLock};
+use serde::Serialize;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use thiserror::Error;
+use tokio::sync::broadcast;
 use tokio::sync::mpsc::{self, Receiver, Sender};
 
+use crate::csv;
+use crate::page::Page;
 use crate::ticket;
 
-use super::ticket::{Ticket, TicketId, TicketPatch};
+use super::ticket::{CustomStatus, Ticket, TicketId, TicketParseError, TicketPatch, TicketStatus};
 
+// How many events a subscriber can fall behind before it starts missing them,
+// unless configured with `TicketStore::with_event_capacity`.
+const DEFAULT_EVENT_CAPACITY: usize = 1024;
+
+// Tickets are kept behind an `Arc` so reads hand out a shared pointer instead
+// of deep-copying titles and descriptions; patches copy on write.
 #[derive(Debug, Clone)]
 pub struct TicketStore {
-    tickets: Arc<RwLock<BTreeMap<TicketId, Ticket>>>,
+    tickets: Arc<RwLock<BTreeMap<TicketId, Arc<Ticket>>>>,
+    events: broadcast::Sender<TicketEvent>,
+    // Where the search for a free id starts when the store picks one itself.
+    next_id: Arc<AtomicU64>,
+    reject_title_as_description: bool,
+    max_tickets: Option<usize>,
+    custom_statuses: Arc<BTreeSet<CustomStatus>>,
+    transitions: Option<Arc<BTreeMap<TicketStatus, BTreeSet<TicketStatus>>>>,
+    counters: Arc<Counters>,
+    arrivals: Arc<Mutex<Arrivals>>,
+    update_window: Option<Duration>,
+    pending_updates: Arc<Mutex<PendingUpdates>>,
+}
+
+// The latest version of each ticket with an `Updated` event on hold, tagged
+// with the hold it belongs to, so a timer left over from an earlier hold
+// can't flush a later one early.
+#[derive(Debug, Default)]
+struct PendingUpdates {
+    next: u64,
+    by_id: BTreeMap<TicketId, (u64, Arc<Ticket>)>,
+}
+
+// When each stored ticket was (last) inserted, as a sequence number, since
+// ids say nothing about that once clients pick them.
+#[derive(Debug, Default)]
+struct Arrivals {
+    next: u64,
+    by_id: BTreeMap<TicketId, u64>,
 }
 
-#[derive(Debug, Error)]
+// Running totals since the store was created, shared by all its clones.
+#[derive(Debug, Default)]
+struct Counters {
+    inserts: AtomicU64,
+    updates: AtomicU64,
+    deletes: AtomicU64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StoreMetrics {
+    pub inserts_total: u64,
+    pub updates_total: u64,
+    pub deletes_total: u64,
+    pub tickets: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum TicketEvent {
+    Created(Arc<Ticket>),
+    Updated(Arc<Ticket>),
+    Deleted(TicketId),
+}
+
+#[derive(Debug, PartialEq, Error)]
 pub enum PatchError {
     #[error("Requested Ticket {0} not found")]
     NotFound(TicketId),
     #[error(transparent)]
     Mismatch(#[from] ticket::TicketUpdateError),
+    #[error("`{0}` is not a status this store knows about")]
+    UnknownStatus(CustomStatus),
+    #[error("Tickets can't move from `{from}` to `{to}`")]
+    Transition {
+        from: TicketStatus,
+        to: TicketStatus,
+    },
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum CasError {
+    #[error("Ticket {0} changed since it was read")]
+    Stale(TicketId),
+    #[error(transparent)]
+    Patch(#[from] PatchError),
+    #[error(transparent)]
+    Rejected(#[from] InsertError),
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchOutcome {
+    Changed,
+    // Every field in the patch already had that value.
+    Unchanged,
+}
+
+#[derive(Debug, PartialEq, Error)]
+#[error("Ticket {0} already exists")]
+pub struct Conflict(pub TicketId);
+
+#[derive(Debug, PartialEq, Error)]
+pub enum InsertError {
+    #[error(transparent)]
+    Conflict(#[from] Conflict),
+    #[error("Ticket {0} has the same title and description")]
+    TitleIsDescription(TicketId),
+    #[error("`{0}` is not a status this store knows about")]
+    UnknownStatus(CustomStatus),
+    #[error("Ticket {0} would be evicted straight away: the store is full of higher ids")]
+    WouldBeEvicted(TicketId),
+}
+
 impl TicketStore {
     pub fn new() -> Self {
+        Self::with_event_capacity(DEFAULT_EVENT_CAPACITY)
+    }
+    // Events are buffered once for all subscribers, so memory stays bounded
+    // by `capacity` however slow they are. A subscriber that falls more than
+    // `capacity` events behind loses the oldest ones: its next `recv` returns
+    // `RecvError::Lagged(n)` with the number it missed, then carries on from
+    // the oldest event still buffered.
+    //
+    // Panics if `capacity` is zero.
+    pub fn with_event_capacity(capacity: usize) -> Self {
+        let (events, _) = broadcast::channel(capacity);
         TicketStore {
             tickets: Arc::new(RwLock::new(BTreeMap::new())),
+            events,
+            next_id: Arc::new(AtomicU64::new(0)),
+            reject_title_as_description: false,
+            max_tickets: None,
+            custom_statuses: Arc::default(),
+            transitions: None,
+            counters: Arc::default(),
+            arrivals: Arc::default(),
+            update_window: None,
+            pending_updates: Arc::default(),
+        }
+    }
+    // Off by default: when enabled, the first update to a ticket holds its
+    // `Updated` event back for `window`, and further updates to that ticket
+    // in the meantime only replace what it will carry. Subscribers then get
+    // one event with the latest version per ticket per window, instead of
+    // one per update. Updates to different tickets are never merged, but
+    // held events can arrive after later events about other tickets.
+    //
+    // Held events are sent from a tokio task. Outside a tokio runtime there's
+    // nothing to send them later, so updates are sent straight away instead.
+    pub fn with_update_coalescing(mut self, window: Duration) -> Self {
+        self.update_window = Some(window);
+        self
+    }
+    // Off by default: when enabled, inserting a ticket whose title is
+    // identical to its description fails with `InsertError::TitleIsDescription`.
+    pub fn reject_title_as_description(mut self, reject: bool) -> Self {
+        self.reject_title_as_description = reject;
+        self
+    }
+    // Ids picked by the store start at `start` instead of 0. Handy for
+    // predictable ids in tests, or for giving each store a disjoint range.
+    pub fn with_start_id(self, start: u64) -> Self {
+        self.next_id.store(start, Ordering::Relaxed);
+        self
+    }
+    // Keeps at most `max` tickets: adding a new id to a full store first
+    // evicts the lowest id, with a `Deleted` event. A ticket whose id is lower
+    // than every stored one would be evicted straight away, so it's rejected
+    // with `InsertError::WouldBeEvicted` instead. Overwriting a stored ticket
+    // never evicts anything.
+    //
+    // Panics if `max` is zero.
+    pub fn with_max_tickets(mut self, max: usize) -> Self {
+        assert!(max > 0, "a store must be able to hold at least one ticket");
+        self.max_tickets = Some(max);
+        self
+    }
+    // Called before adding the new id `incoming`, see `with_max_tickets`.
+    fn make_room(
+        &self,
+        tickets: &mut BTreeMap<TicketId, Arc<Ticket>>,
+        incoming: TicketId,
+    ) -> Result<Option<Arc<Ticket>>, InsertError> {
+        let Some(max) = self.max_tickets else {
+            return Ok(None);
+        };
+        if tickets.len() >= max
+            && tickets
+                .first_key_value()
+                .is_some_and(|(&lowest, _)| incoming < lowest)
+        {
+            return Err(InsertError::WouldBeEvicted(incoming));
+        }
+        let mut evicted = None;
+        while tickets.len() >= max {
+            let Some((id, ticket)) = tickets.pop_first() else {
+                break;
+            };
+            self.emit(TicketEvent::Deleted(id));
+            evicted = Some(ticket);
         }
+        Ok(evicted)
+    }
+    // Custom statuses tickets may use in this store. The built-in ones are
+    // always allowed.
+    pub fn with_custom_statuses(
+        mut self,
+        statuses: impl IntoIterator<Item = CustomStatus>,
+    ) -> Self {
+        self.custom_statuses = Arc::new(statuses.into_iter().collect());
+        self
+    }
+    // Reads back a status as `Display` writes it. Custom names only parse if
+    // this store registered them.
+    pub fn parse_status(&self, name: &str) -> Result<TicketStatus, TicketParseError> {
+        TicketStatus::try_from(name).or_else(|err| match CustomStatus::new(name) {
+            Ok(custom) if self.custom_statuses.contains(&custom) => {
+                Ok(TicketStatus::Custom(custom))
+            }
+            _ => Err(err),
+        })
+    }
+    // Without rules any status can follow any other. With them, a ticket in
+    // a status that has an entry can only be patched to a status listed
+    // there; statuses without an entry stay unrestricted.
+    pub fn with_transitions(
+        mut self,
+        rules: BTreeMap<TicketStatus, BTreeSet<TicketStatus>>,
+    ) -> Self {
+        self.transitions = Some(Arc::new(rules));
+        self
     }
-    pub fn get(&self, id: TicketId) -> Option<Ticket> {
+    fn check_policy(&self, ticket: &Ticket) -> Result<(), InsertError> {
+        if self.reject_title_as_description && ticket.title.as_str() == ticket.description.as_str()
+        {
+            return Err(InsertError::TitleIsDescription(ticket.id));
+        }
+        if let TicketStatus::Custom(custom) = ticket.status {
+            if !self.custom_statuses.contains(&custom) {
+                return Err(InsertError::UnknownStatus(custom));
+            }
+        }
+        Ok(())
+    }
+    fn check_transition(&self, from: TicketStatus, to: TicketStatus) -> Result<(), PatchError> {
+        if let TicketStatus::Custom(custom) = to {
+            if !self.custom_statuses.contains(&custom) {
+                return Err(PatchError::UnknownStatus(custom));
+            }
+        }
+        let allowed = from == to
+            || self
+                .transitions
+                .as_ref()
+                .and_then(|rules| rules.get(&from))
+                .is_none_or(|next| next.contains(&to));
+        if !allowed {
+            return Err(PatchError::Transition { from, to });
+        }
+        Ok(())
+    }
+    // Ids can also be chosen by clients, so skip over any that are taken.
+    // Only call this while holding the write lock, or the id may be gone by
+    // the time it's used.
+    fn next_free_id(&self, tickets: &BTreeMap<TicketId, Arc<Ticket>>) -> TicketId {
+        loop {
+            let id = TicketId::from(self.next_id.fetch_add(1, Ordering::Relaxed));
+            if !tickets.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+    pub fn subscribe(&self) -> broadcast::Receiver<TicketEvent> {
+        self.events.subscribe()
+    }
+    // Events still buffered because some subscriber hasn't received them yet,
+    // so a growing backlog means a subscriber is falling behind.
+    pub fn event_backlog(&self) -> usize {
+        self.events.len()
+    }
+    // Every change goes through here, so this is also where it's counted.
+    // Sending only fails when nobody is subscribed, which is fine.
+    fn emit(&self, event: TicketEvent) {
+        let counter = match &event {
+            TicketEvent::Created(ticket) => {
+                let mut arrivals = self.arrivals.lock();
+                let seq = arrivals.next;
+                arrivals.next += 1;
+                arrivals.by_id.insert(ticket.id, seq);
+                &self.counters.inserts
+            }
+            TicketEvent::Updated(_) => &self.counters.updates,
+            TicketEvent::Deleted(id) => {
+                self.arrivals.lock().by_id.remove(id);
+                &self.counters.deletes
+            }
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        if let Some(window) = self.update_window {
+            match &event {
+                TicketEvent::Updated(ticket) => {
+                    self.hold_update(ticket.clone(), window);
+                    return;
+                }
+                // An update sent after its ticket's deletion would bring it back.
+                TicketEvent::Deleted(id) => {
+                    self.pending_updates.lock().by_id.remove(id);
+                }
+                TicketEvent::Created(_) => {}
+            }
+        }
+        let _ = self.events.send(event);
+    }
+    fn hold_update(&self, ticket: Arc<Ticket>, window: Duration) {
+        let id = ticket.id;
+        let mut pending = self.pending_updates.lock();
+        if let Some((_, held)) = pending.by_id.get_mut(&id) {
+            // Already on hold: the event that's due will carry this version.
+            *held = ticket;
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            let _ = self.events.send(TicketEvent::Updated(ticket));
+            return;
+        };
+        let hold = pending.next;
+        pending.next += 1;
+        pending.by_id.insert(id, (hold, ticket));
+        drop(pending);
+
+        let pending = self.pending_updates.clone();
+        let events = self.events.clone();
+        runtime.spawn(async move {
+            tokio::time::sleep(window).await;
+            let mut pending = pending.lock();
+            if let Entry::Occupied(entry) = pending.by_id.entry(id) {
+                // Otherwise the ticket was deleted and held again since.
+                if entry.get().0 == hold {
+                    let (_, ticket) = entry.remove();
+                    let _ = events.send(TicketEvent::Updated(ticket));
+                }
+            }
+        });
+    }
+    // Tickets per status, in workflow order (`TicketStatus`'s `Ord`). Every
+    // built-in and registered custom status has an entry, zero or not, so
+    // the shape doesn't depend on what happens to be stored.
+    pub fn status_counts(&self) -> BTreeMap<TicketStatus, usize> {
+        let built_in = [
+            TicketStatus::ToDo,
+            TicketStatus::InProgress,
+            TicketStatus::Done,
+        ];
+        let custom = self
+            .custom_statuses
+            .iter()
+            .map(|&c| TicketStatus::Custom(c));
+        let mut counts: BTreeMap<TicketStatus, usize> = built_in
+            .into_iter()
+            .chain(custom)
+            .map(|status| (status, 0))
+            .collect();
+        for ticket in self.tickets.read().values() {
+            *counts.entry(ticket.status).or_default() += 1;
+        }
+        counts
+    }
+    // `5 tickets: 2 todo, 1 in progress, 2 done`, for logs and terminals.
+    // Registered custom statuses follow the built-in ones.
+    pub fn summary_line(&self) -> String {
+        let counts = self.status_counts();
+        let total: usize = counts.values().sum();
+        let noun = if total == 1 { "ticket" } else { "tickets" };
+        let parts: Vec<String> = counts
+            .iter()
+            .map(|(status, count)| match status {
+                TicketStatus::InProgress => format!("{} in progress", count),
+                status => format!("{} {}", count, status),
+            })
+            .collect();
+        format!("{} {}: {}", total, noun, parts.join(", "))
+    }
+    pub fn metrics(&self) -> StoreMetrics {
+        StoreMetrics {
+            inserts_total: self.counters.inserts.load(Ordering::Relaxed),
+            updates_total: self.counters.updates.load(Ordering::Relaxed),
+            deletes_total: self.counters.deletes.load(Ordering::Relaxed),
+            tickets: self.tickets.read().len(),
+        }
+    }
+    // Only bumps one refcount, so point lookups stay cheap.
+    pub fn get(&self, id: TicketId) -> Option<Arc<Ticket>> {
         self.tickets.read().get(&id).cloned()
     }
-    pub fn insert(&self, ticket: Ticket){
-        self.tickets.write().insert(ticket.id, ticket);
+    // Copies every ticket handle into a `Vec` under a single read lock, so the
+    // listing is one consistent view of the store: writers wait until the
+    // copy is done and anything they change afterwards goes into a new `Arc`
+    // rather than the ones handed out. The cost is one `Arc` clone per ticket
+    // plus the `Vec`, paid while writers are blocked.
+    pub fn list(&self) -> Vec<Arc<Ticket>> {
+        self.tickets.read().values().cloned().collect()
+    }
+    // `limit` tickets in id order from `offset` on. The total is counted
+    // under the same read lock, so it always matches the page.
+    pub fn page(&self, offset: usize, limit: usize) -> Page<Arc<Ticket>> {
+        let tickets = self.tickets.read();
+        Page {
+            items: tickets.values().skip(offset).take(limit).cloned().collect(),
+            total: tickets.len(),
+            offset,
+            limit,
+        }
+    }
+    // A Fisher-Yates shuffle driven by `seed`, so the same seed always gives
+    // the same order for the same tickets.
+    pub fn list_shuffled(&self, seed: u64) -> Vec<Arc<Ticket>> {
+        let mut tickets = self.list();
+        let mut state = seed;
+        for i in (1..tickets.len()).rev() {
+            let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+            tickets.swap(i, j);
+        }
+        tickets
+    }
+    // Tickets are shared with the store, so this is cheap however big they are.
+    pub fn snapshot(&self) -> StoreSnapshot {
+        StoreSnapshot {
+            tickets: self.tickets.read().clone(),
+        }
+    }
+    // Oldest insert first. Overwriting a ticket with `insert` counts as
+    // inserting it again.
+    pub fn by_insertion_order(&self) -> Vec<Arc<Ticket>> {
+        let tickets = self.tickets.read();
+        let arrivals = self.arrivals.lock();
+        let mut ordered: Vec<Arc<Ticket>> = tickets.values().cloned().collect();
+        ordered.sort_by_key(|ticket| arrivals.by_id.get(&ticket.id).copied());
+        ordered
+    }
+    pub fn insert(&self, ticket: Ticket) -> Result<(), InsertError> {
+        self.insert_evicting(ticket).map(|_| ())
+    }
+    // Like `insert`, but also hands back the ticket evicted to make room,
+    // if the store was created `with_max_tickets` and is full.
+    pub fn insert_evicting(&self, ticket: Ticket) -> Result<Option<Arc<Ticket>>, InsertError> {
+        self.check_policy(&ticket)?;
+        let mut tickets = self.tickets.write();
+        let evicted = if tickets.contains_key(&ticket.id) {
+            None
+        } else {
+            self.make_room(&mut tickets, ticket.id)?
+        };
+        let ticket = Arc::new(ticket);
+        tickets.insert(ticket.id, ticket.clone());
+        self.emit(TicketEvent::Created(ticket));
+        Ok(evicted)
+    }
+    // Unlike `insert`, never overwrites a ticket that is already stored.
+    pub fn insert_new(&self, ticket: Ticket) -> Result<(), InsertError> {
+        self.check_policy(&ticket)?;
+        let mut tickets = self.tickets.write();
+        if tickets.contains_key(&ticket.id) {
+            return Err(Conflict(ticket.id).into());
+        }
+        self.make_room(&mut tickets, ticket.id)?;
+        let ticket = Arc::new(ticket);
+        tickets.insert(ticket.id, ticket.clone());
+        self.emit(TicketEvent::Created(ticket));
+        Ok(())
+    }
+    // Copies ticket `id` into a new ticket with a fresh id and a `ToDo` status,
+    // returning the new id, or `None` if `id` doesn't exist. In a full store
+    // the copy can also be refused, if its fresh id would be the one evicted
+    // (see `with_max_tickets`).
+    pub fn duplicate(&self, id: TicketId) -> Option<TicketId> {
+        let mut tickets = self.tickets.write();
+        let source = tickets.get(&id)?;
+        let mut copy = Ticket::clone(source);
+        copy.id = self.next_free_id(&tickets);
+        copy.status = TicketStatus::ToDo;
+        copy.created_at = SystemTime::now();
+        copy.updated_at = copy.created_at;
+        self.make_room(&mut tickets, copy.id).ok()?;
+        let copy = Arc::new(copy);
+        tickets.insert(copy.id, copy.clone());
+        self.emit(TicketEvent::Created(copy.clone()));
+        Some(copy.id)
     }
-    pub fn patch(&self, id: TicketId, p: TicketPatch) -> Result<(), PatchError> {
+    // A patch that only repeats current values leaves the ticket, including
+    // `updated_at`, alone and emits no event.
+    pub fn patch(&self, id: TicketId, p: TicketPatch) -> Result<PatchOutcome, PatchError> {
+        self.patch_locked(&mut self.tickets.write(), id, p)
+    }
+    // Applies every patch to the ticket named by its own id, under one write
+    // lock, so nobody sees the batch half-applied. Failed patches don't stop
+    // the rest; results are in the same order as `patches`.
+    pub fn patch_many(&self, patches: Vec<TicketPatch>) -> Vec<Result<PatchOutcome, PatchError>> {
+        let mut tickets = self.tickets.write();
+        patches
+            .into_iter()
+            .map(|p| self.patch_locked(&mut tickets, p.id, p))
+            .collect()
+    }
+    // Replaces ticket `id` with `new`, but only if it still equals `expected`
+    // (usually what an earlier `get` returned), so concurrent editors can't
+    // silently overwrite each other. `new` goes through the same checks as
+    // an insert and a patch, keeps the stored `created_at` and gets a fresh
+    // `updated_at`.
+    pub fn compare_and_set(
+        &self,
+        id: TicketId,
+        expected: &Ticket,
+        mut new: Ticket,
+    ) -> Result<(), CasError> {
+        if new.id != id {
+            let mismatch = ticket::TicketUpdateError {
+                original_id: id,
+                patch_id: new.id,
+            };
+            return Err(PatchError::from(mismatch).into());
+        }
+        self.check_policy(&new)?;
         let mut tickets = self.tickets.write();
+        let current = tickets.get_mut(&id).ok_or(PatchError::NotFound(id))?;
+        if **current != *expected {
+            return Err(CasError::Stale(id));
+        }
+        self.check_transition(current.status, new.status)?;
+        new.created_at = current.created_at;
+        new.updated_at = SystemTime::now();
+        *current = Arc::new(new);
+        self.emit(TicketEvent::Updated(current.clone()));
+        Ok(())
+    }
+    fn patch_locked(
+        &self,
+        tickets: &mut BTreeMap<TicketId, Arc<Ticket>>,
+        id: TicketId,
+        p: TicketPatch,
+    ) -> Result<PatchOutcome, PatchError> {
         if let Some(existing_ticket) = tickets.get_mut(&id) {
-            existing_ticket.update(p)?;
-            Ok(())
+            if let Some(status) = p.status {
+                self.check_transition(existing_ticket.status, status)?;
+            }
+            if p.id == existing_ticket.id && p.changes_nothing(existing_ticket) {
+                return Ok(PatchOutcome::Unchanged);
+            }
+            Arc::make_mut(existing_ticket).update(p)?;
+            self.emit(TicketEvent::Updated(existing_ticket.clone()));
+            Ok(PatchOutcome::Changed)
         } else {
             Err(PatchError::NotFound(id))
         }
     }
+    // Removes every ticket in `status`, returning how many were removed.
+    pub fn delete_by_status(&self, status: TicketStatus) -> usize {
+        let mut tickets = self.tickets.write();
+        let doomed: Vec<TicketId> = tickets
+            .values()
+            .filter(|ticket| ticket.status == status)
+            .map(|ticket| ticket.id)
+            .collect();
+        for id in &doomed {
+            tickets.remove(id);
+            self.emit(TicketEvent::Deleted(*id));
+        }
+        doomed.len()
+    }
+    // Tickets whose last update was more than `older_than` before `now`.
+    pub fn stale(&self, older_than: Duration, now: SystemTime) -> Vec<Arc<Ticket>> {
+        self.tickets
+            .read()
+            .values()
+            .filter(|ticket| {
+                now.duration_since(ticket.updated_at)
+                    .is_ok_and(|since| since > older_than)
+            })
+            .cloned()
+            .collect()
+    }
+    // Up to `max` tickets ranked by how few single-character edits turn their
+    // title into `q`, ignoring case. Ties keep id order.
+    pub fn similar_titles(&self, q: &str, max: usize) -> Vec<Arc<Ticket>> {
+        let q = q.to_lowercase();
+        let mut ranked: Vec<(usize, Arc<Ticket>)> = self
+            .tickets
+            .read()
+            .values()
+            .map(|ticket| {
+                let distance = levenshtein(&ticket.title.as_str().to_lowercase(), &q);
+                (distance, ticket.clone())
+            })
+            .collect();
+        ranked.sort_by_key(|(distance, _)| *distance);
+        ranked
+            .into_iter()
+            .take(max)
+            .map(|(_, ticket)| ticket)
+            .collect()
+    }
+    pub fn to_csv(&self) -> String {
+        let tickets = self.tickets.read();
+        let mut csv = format!("{}\n", CSV_HEADER);
+        for ticket in tickets.values() {
+            csv.push_str(&format!(
+                "{},{},{},{}\n",
+                ticket.id,
+                ticket.status,
+                csv::escape_field(ticket.title.as_str()),
+                csv::escape_field(ticket.description.as_str()),
+            ));
+        }
+        csv
+    }
+    // Imports tickets in the format produced by `to_csv` (the header row is
+    // optional). Rows that don't parse, fail validation or reuse an existing
+    // id are rejected with a reason while the others are inserted; with
+    // `strict` a single rejected row means nothing is inserted. In a full
+    // store, rows whose ids would be evicted again straight away are
+    // rejected too (see `with_max_tickets`).
+    pub fn import_csv(&self, input: &str, strict: bool) -> ImportSummary {
+        let mut tickets = self.tickets.write();
+        // By id, each with the row it came from.
+        let mut accepted: BTreeMap<TicketId, (usize, Ticket)> = BTreeMap::new();
+        let mut rejected = Vec::new();
+        for (index, record) in csv::parse_records(input).into_iter().enumerate() {
+            let row = index + 1;
+            if row == 1
+                && record
+                    .as_ref()
+                    .is_ok_and(|fields| fields.join(",") == CSV_HEADER)
+            {
+                continue;
+            }
+            let ticket = record
+                .and_then(|fields| self.parse_csv_ticket(fields))
+                .and_then(|ticket| {
+                    self.check_policy(&ticket).map_err(|e| e.to_string())?;
+                    if tickets.contains_key(&ticket.id) || accepted.contains_key(&ticket.id) {
+                        return Err(Conflict(ticket.id).to_string());
+                    }
+                    Ok(ticket)
+                });
+            match ticket {
+                Ok(ticket) => {
+                    accepted.insert(ticket.id, (row, ticket));
+                }
+                Err(reason) => rejected.push(RejectedRow { row, reason }),
+            }
+        }
+        if let Some(max) = self.max_tickets {
+            // Whatever ends up among the lowest `overflow` ids is evicted.
+            let overflow = (tickets.len() + accepted.len()).saturating_sub(max);
+            let mut ids: Vec<TicketId> = tickets.keys().chain(accepted.keys()).copied().collect();
+            ids.sort();
+            for id in ids.into_iter().take(overflow) {
+                if let Some((row, _)) = accepted.remove(&id) {
+                    let reason = InsertError::WouldBeEvicted(id).to_string();
+                    rejected.push(RejectedRow { row, reason });
+                }
+            }
+            rejected.sort_by_key(|rejection| rejection.row);
+        }
+        if strict && !rejected.is_empty() {
+            return ImportSummary {
+                imported: 0,
+                rejected,
+            };
+        }
+        let imported = accepted.len();
+        for (id, (_, ticket)) in accepted {
+            // Every stored id the import evicts is lower than every
+            // accepted one, so they're always the ones to go.
+            self.make_room(&mut tickets, id)
+                .expect("rows that would be evicted were rejected above");
+            let ticket = Arc::new(ticket);
+            tickets.insert(id, ticket.clone());
+            self.emit(TicketEvent::Created(ticket));
+        }
+        ImportSummary { imported, rejected }
+    }
+}
+
+// The store's tickets as they were when `TicketStore::snapshot` was called.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoreSnapshot {
+    tickets: BTreeMap<TicketId, Arc<Ticket>>,
+}
+
+// Ids in ascending order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StoreDiff {
+    pub added: Vec<TicketId>,
+    pub removed: Vec<TicketId>,
+    pub changed: Vec<TicketId>,
+}
+
+impl StoreSnapshot {
+    // What happened between this snapshot and a later `other`.
+    pub fn diff(&self, other: &StoreSnapshot) -> StoreDiff {
+        let mut diff = StoreDiff::default();
+        for (id, ticket) in &self.tickets {
+            match other.tickets.get(id) {
+                None => diff.removed.push(*id),
+                Some(later) if later != ticket => diff.changed.push(*id),
+                Some(_) => {}
+            }
+        }
+        diff.added = other
+            .tickets
+            .keys()
+            .filter(|id| !self.tickets.contains_key(id))
+            .copied()
+            .collect();
+        diff
+    }
+}
+
+// A tiny, well-mixed pseudo-random generator; not for anything secret.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// Edit distance between `a` and `b`, keeping only one row of the table.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != *b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+const CSV_HEADER: &str = "id,status,title,description";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub rejected: Vec<RejectedRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RejectedRow {
+    // 1-based, counting the header row if there is one.
+    pub row: usize,
+    pub reason: String,
+}
+
+impl TicketStore {
+    fn parse_csv_ticket(&self, fields: Vec<String>) -> Result<Ticket, String> {
+        let [id, status, title, description]: [String; 4] = fields
+            .try_into()
+            .map_err(|fields: Vec<String>| format!("expected 4 fields, found {}", fields.len()))?;
+        let id: u64 = id
+            .parse()
+            .map_err(|_| format!("`{}` is not a valid ticket id", id))?;
+        let status = self.parse_status(&status).map_err(|e| e.to_string())?;
+        let title = title
+            .try_into()
+            .map_err(|e: TicketParseError| e.to_string())?;
+        let description = description
+            .try_into()
+            .map_err(|e: TicketParseError| e.to_string())?;
+        Ok(Ticket::new(id.into(), title, description, status))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ticket::TicketStatus;
 
     fn get_ticket() -> Ticket {
         Ticket::new(
@@ -61,19 +806,802 @@ mod tests {
     fn test_ticket_insert_get() {
         let store = TicketStore::new();
         let ticket = get_ticket();
-        store.insert(ticket.clone());
+        store.insert(ticket.clone()).unwrap();
+
+        assert_eq!(store.get(ticket.id).as_deref(), Some(&ticket));
+    }
+    #[test]
+    fn test_title_as_description_policy() {
+        let ticket = Ticket::new(
+            42.into(),
+            "same text".try_into().unwrap(),
+            "same text".try_into().unwrap(),
+            "todo".try_into().unwrap(),
+        );
+
+        let store = TicketStore::new();
+        assert_eq!(store.insert(ticket.clone()), Ok(()));
+
+        let store = TicketStore::new().reject_title_as_description(true);
+        assert_eq!(
+            store.insert(ticket.clone()),
+            Err(InsertError::TitleIsDescription(ticket.id))
+        );
+        assert_eq!(
+            store.insert_new(ticket.clone()),
+            Err(InsertError::TitleIsDescription(ticket.id))
+        );
+        assert_eq!(store.get(ticket.id), None);
+        assert_eq!(store.insert(get_ticket()), Ok(()));
+    }
+    #[test]
+    fn test_ticket_get_shares_allocation() {
+        let store = TicketStore::new();
+        let ticket = get_ticket();
+        store.insert(ticket.clone()).unwrap();
+
+        let first = store.get(ticket.id).unwrap();
+        let second = store.get(ticket.id).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+    #[test]
+    fn test_ticket_patch_copies_on_write() {
+        let store = TicketStore::new();
+        let ticket = get_ticket();
+        store.insert(ticket.clone()).unwrap();
 
-        assert_eq!(store.get(ticket.id), Some(ticket.clone()));
+        let before = store.get(ticket.id).unwrap();
+        let patch = TicketPatch::new(ticket.id, None, None, Some(TicketStatus::Done));
+        store.patch(ticket.id, patch).unwrap();
+        let after = store.get(ticket.id).unwrap();
+
+        assert_eq!(before.status, TicketStatus::ToDo);
+        assert_eq!(after.status, TicketStatus::Done);
+        assert!(!Arc::ptr_eq(&before, &after));
+    }
+    #[test]
+    fn test_ticket_insert_new() {
+        let store = TicketStore::new();
+        let ticket = get_ticket();
+        assert_eq!(store.insert_new(ticket.clone()), Ok(()));
+        assert_eq!(store.get(ticket.id).as_deref(), Some(&ticket));
+    }
+    #[test]
+    fn test_ticket_insert_new_conflict() {
+        let store = TicketStore::new();
+        let ticket = get_ticket();
+        store.insert(ticket.clone()).unwrap();
+
+        let mut duplicate = get_ticket();
+        duplicate.status = TicketStatus::Done;
+        assert_eq!(
+            store.insert_new(duplicate),
+            Err(InsertError::Conflict(Conflict(ticket.id)))
+        );
+        assert_eq!(store.get(ticket.id).as_deref(), Some(&ticket));
     }
     #[test]
     fn test_ticket_patch() {
         let store = TicketStore::new();
         let ticket = get_ticket();
-        store.insert(ticket.clone());
+        store.insert(ticket.clone()).unwrap();
 
         let patch = TicketPatch::new(ticket.id, None, None, Some(TicketStatus::Done));
         store.patch(ticket.id, patch).unwrap();
 
         assert_eq!(store.get(ticket.id).unwrap().status, TicketStatus::Done);
     }
+    #[test]
+    fn test_ticket_events() {
+        let store = TicketStore::new();
+        let mut events = store.subscribe();
+        let ticket = get_ticket();
+        store.insert(ticket.clone()).unwrap();
+        let patch = TicketPatch::new(ticket.id, None, None, Some(TicketStatus::Done));
+        store.patch(ticket.id, patch).unwrap();
+
+        assert_eq!(
+            events.try_recv().unwrap(),
+            TicketEvent::Created(Arc::new(ticket.clone()))
+        );
+        let mut updated = ticket;
+        updated.status = TicketStatus::Done;
+        updated.updated_at = store.get(updated.id).unwrap().updated_at;
+        assert_eq!(
+            events.try_recv().unwrap(),
+            TicketEvent::Updated(Arc::new(updated))
+        );
+    }
+    #[tokio::test]
+    async fn test_update_coalescing() {
+        let store = TicketStore::new().with_update_coalescing(Duration::from_millis(50));
+        let mut other = get_ticket();
+        other.id = 43.into();
+        store.insert(get_ticket()).unwrap();
+        store.insert(other).unwrap();
+        let mut events = store.subscribe();
+
+        let status = |status| TicketPatch::new(42.into(), None, None, Some(status));
+        store
+            .patch(42.into(), status(TicketStatus::InProgress))
+            .unwrap();
+        store.patch(42.into(), status(TicketStatus::Done)).unwrap();
+        let patch = TicketPatch::new(43.into(), None, None, Some(TicketStatus::Done));
+        store.patch(43.into(), patch).unwrap();
+        assert!(events.try_recv().is_err());
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let mut updated = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            let TicketEvent::Updated(ticket) = event else {
+                panic!("expected only updates, got {:?}", event);
+            };
+            updated.push((ticket.id, ticket.status));
+        }
+        updated.sort();
+        assert_eq!(
+            updated,
+            vec![
+                (42.into(), TicketStatus::Done),
+                (43.into(), TicketStatus::Done)
+            ]
+        );
+        assert_eq!(store.metrics().updates_total, 3);
+    }
+    #[tokio::test(start_paused = true)]
+    async fn test_update_coalescing_ignores_stale_timers() {
+        let store = TicketStore::new().with_update_coalescing(Duration::from_millis(50));
+        store.insert(get_ticket()).unwrap();
+        let mut events = store.subscribe();
+        let done = || TicketPatch::new(42.into(), None, None, Some(TicketStatus::Done));
+
+        store.patch(42.into(), done()).unwrap();
+        store.delete_by_status(TicketStatus::Done);
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        store.insert(get_ticket()).unwrap();
+        store.patch(42.into(), done()).unwrap();
+        assert_eq!(events.try_recv().unwrap(), TicketEvent::Deleted(42.into()));
+        assert!(matches!(events.try_recv(), Ok(TicketEvent::Created(_))));
+
+        // The first hold's timer has fired by now, but the second hold
+        // still has 20ms to go.
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(events.try_recv().is_err());
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        let Ok(TicketEvent::Updated(ticket)) = events.try_recv() else {
+            panic!("expected the held update");
+        };
+        assert_eq!(ticket.status, TicketStatus::Done);
+    }
+    #[test]
+    fn test_update_coalescing_without_a_runtime() {
+        let store = TicketStore::new().with_update_coalescing(Duration::from_secs(60));
+        store.insert(get_ticket()).unwrap();
+        let mut events = store.subscribe();
+
+        let patch = TicketPatch::new(42.into(), None, None, Some(TicketStatus::Done));
+        store.patch(42.into(), patch).unwrap();
+        let Ok(TicketEvent::Updated(ticket)) = events.try_recv() else {
+            panic!("expected the update to be sent right away");
+        };
+        assert_eq!(ticket.status, TicketStatus::Done);
+    }
+    #[tokio::test]
+    async fn test_update_coalescing_drops_updates_of_deleted_tickets() {
+        let store = TicketStore::new().with_update_coalescing(Duration::from_millis(20));
+        store.insert(get_ticket()).unwrap();
+        let mut events = store.subscribe();
+
+        let patch = TicketPatch::new(42.into(), None, None, Some(TicketStatus::Done));
+        store.patch(42.into(), patch).unwrap();
+        store.delete_by_status(TicketStatus::Done);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(events.try_recv().unwrap(), TicketEvent::Deleted(42.into()));
+        assert!(events.try_recv().is_err());
+    }
+    #[test]
+    fn test_duplicate() {
+        let store = TicketStore::new();
+        let mut ticket = get_ticket();
+        ticket.id = 0.into();
+        ticket.status = TicketStatus::Done;
+        store.insert(ticket.clone()).unwrap();
+
+        let copy_id = store.duplicate(ticket.id).unwrap();
+        assert_ne!(copy_id, ticket.id);
+        let copy = store.get(copy_id).unwrap();
+        assert_eq!(copy.title, ticket.title);
+        assert_eq!(copy.description, ticket.description);
+        assert_eq!(copy.status, TicketStatus::ToDo);
+        assert_eq!(store.get(ticket.id).unwrap().status, TicketStatus::Done);
+
+        assert_eq!(store.duplicate(999.into()), None);
+    }
+    #[test]
+    fn test_stale() {
+        let store = TicketStore::new();
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut old = get_ticket();
+        old.updated_at = start;
+        let mut fresh = get_ticket();
+        fresh.id = 43.into();
+        fresh.updated_at = start + Duration::from_secs(3_000);
+        store.insert(old.clone()).unwrap();
+        store.insert(fresh).unwrap();
+
+        let now = start + Duration::from_secs(3_600);
+        let stale = store.stale(Duration::from_secs(1_800), now);
+        assert_eq!(stale, vec![Arc::new(old)]);
+        assert!(store.stale(Duration::from_secs(7_200), now).is_empty());
+    }
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("abc", ""), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("flaw", "lawn"), 2);
+    }
+    #[test]
+    fn test_similar_titles() {
+        let store = TicketStore::new();
+        for (id, title) in [
+            (1, "Fix the deploy script"),
+            (2, "Login page crashes"),
+            (3, "Update docs"),
+        ] {
+            let mut ticket = get_ticket();
+            ticket.id = id.into();
+            ticket.title = title.try_into().unwrap();
+            store.insert(ticket).unwrap();
+        }
+
+        let similar = store.similar_titles("login page crash", 2);
+        let ids: Vec<TicketId> = similar.iter().map(|ticket| ticket.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[0], 2.into());
+        assert!(store.similar_titles("anything", 0).is_empty());
+    }
+    #[test]
+    fn test_snapshot_diff() {
+        let store = TicketStore::new();
+        for id in 1..=3 {
+            let mut ticket = get_ticket();
+            ticket.id = id.into();
+            store.insert(ticket).unwrap();
+        }
+        let before = store.snapshot();
+
+        let mut added = get_ticket();
+        added.id = 4.into();
+        store.insert(added).unwrap();
+        // There's no single-ticket delete, so mark it done first.
+        let patch = TicketPatch::new(3.into(), None, None, Some(TicketStatus::Done));
+        store.patch(3.into(), patch).unwrap();
+        store.delete_by_status(TicketStatus::Done);
+        let patch = TicketPatch::new(1.into(), None, None, Some(TicketStatus::InProgress));
+        store.patch(1.into(), patch).unwrap();
+        let after = store.snapshot();
+
+        assert_eq!(
+            before.diff(&after),
+            StoreDiff {
+                added: vec![4.into()],
+                removed: vec![3.into()],
+                changed: vec![1.into()],
+            }
+        );
+        assert_eq!(after.diff(&after), StoreDiff::default());
+    }
+    #[test]
+    fn test_list_shuffled() {
+        let store = TicketStore::new();
+        for id in 0..10 {
+            let mut ticket = get_ticket();
+            ticket.id = id.into();
+            store.insert(ticket).unwrap();
+        }
+        let ids = |tickets: Vec<Arc<Ticket>>| -> Vec<TicketId> {
+            tickets.iter().map(|ticket| ticket.id).collect()
+        };
+
+        let shuffled = ids(store.list_shuffled(1));
+        assert_eq!(shuffled, ids(store.list_shuffled(1)));
+        assert_ne!(shuffled, ids(store.list_shuffled(2)));
+        let mut sorted = shuffled.clone();
+        sorted.sort();
+        assert_eq!(sorted, ids(store.list()));
+    }
+    #[test]
+    fn test_max_tickets_evicts_lowest_id() {
+        let store = TicketStore::new().with_max_tickets(3);
+        let mut events = store.subscribe();
+        for id in 1..=3 {
+            let mut ticket = get_ticket();
+            ticket.id = id.into();
+            assert_eq!(store.insert_evicting(ticket), Ok(None));
+        }
+
+        let mut ticket = get_ticket();
+        ticket.id = 4.into();
+        let evicted = store.insert_evicting(ticket).unwrap().unwrap();
+        assert_eq!(evicted.id, 1.into());
+        assert_eq!(store.list().len(), 3);
+        assert_eq!(store.get(1.into()), None);
+        let drain = |events: &mut broadcast::Receiver<TicketEvent>| {
+            std::iter::from_fn(|| events.try_recv().ok()).collect::<Vec<_>>()
+        };
+        let recent = drain(&mut events);
+        assert_eq!(recent.len(), 5);
+        assert_eq!(recent[3], TicketEvent::Deleted(1.into()));
+        assert!(matches!(&recent[4], TicketEvent::Created(ticket) if ticket.id == 4.into()));
+
+        // Overwriting a stored ticket doesn't grow the store, so nothing is
+        // evicted. It still counts as inserting it again.
+        let mut ticket = get_ticket();
+        ticket.id = 2.into();
+        assert_eq!(store.insert_evicting(ticket), Ok(None));
+        assert_eq!(store.list().len(), 3);
+        let recent = drain(&mut events);
+        assert!(matches!(&recent[..], [TicketEvent::Created(ticket)] if ticket.id == 2.into()));
+
+        // A ticket below every stored id would be the one evicted.
+        let mut ticket = get_ticket();
+        ticket.id = 0.into();
+        assert_eq!(
+            store.insert_evicting(ticket.clone()),
+            Err(InsertError::WouldBeEvicted(0.into()))
+        );
+        assert_eq!(
+            store.insert_new(ticket),
+            Err(InsertError::WouldBeEvicted(0.into()))
+        );
+        assert_eq!(store.get(0.into()), None);
+        assert!(drain(&mut events).is_empty());
+        let ids: Vec<TicketId> = store.list().iter().map(|ticket| ticket.id).collect();
+        assert_eq!(ids, vec![2.into(), 3.into(), 4.into()]);
+    }
+    #[test]
+    fn test_max_tickets_import_csv() {
+        let store = TicketStore::new().with_max_tickets(2);
+        for id in [5, 6] {
+            let mut ticket = get_ticket();
+            ticket.id = id.into();
+            store.insert(ticket).unwrap();
+        }
+
+        let input = "1,todo,a title,a description
+7,todo,a title,a description
+";
+        let summary = store.import_csv(input, false);
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.rejected.len(), 1);
+        assert_eq!(summary.rejected[0].row, 1);
+        assert!(summary.rejected[0].reason.contains("evicted"));
+        let ids: Vec<TicketId> = store.list().iter().map(|ticket| ticket.id).collect();
+        assert_eq!(ids, vec![6.into(), 7.into()]);
+
+        let input = "3,todo,a title,a description
+";
+        assert_eq!(store.import_csv(input, true).imported, 0);
+        assert_eq!(store.list().len(), 2);
+    }
+    #[test]
+    fn test_metrics() {
+        let store = TicketStore::new();
+        let ticket = get_ticket();
+        store.insert(ticket.clone()).unwrap();
+        store.duplicate(ticket.id).unwrap();
+        let patch = TicketPatch::new(ticket.id, None, None, Some(TicketStatus::Done));
+        store.patch(ticket.id, patch).unwrap();
+        store.delete_by_status(TicketStatus::Done);
+
+        assert_eq!(
+            store.clone().metrics(),
+            StoreMetrics {
+                inserts_total: 2,
+                updates_total: 1,
+                deletes_total: 1,
+                tickets: 1,
+            }
+        );
+    }
+    #[test]
+    fn test_status_counts() {
+        let blocked = CustomStatus::new("blocked").unwrap();
+        let store = TicketStore::new().with_custom_statuses([blocked]);
+        for (id, status) in [(1, TicketStatus::Done), (2, TicketStatus::Done)] {
+            let mut ticket = get_ticket();
+            ticket.id = id.into();
+            ticket.status = status;
+            store.insert(ticket).unwrap();
+        }
+
+        let counts: Vec<_> = store.status_counts().into_iter().collect();
+        assert_eq!(
+            counts,
+            vec![
+                (TicketStatus::ToDo, 0),
+                (TicketStatus::InProgress, 0),
+                (TicketStatus::Done, 2),
+                (TicketStatus::Custom(blocked), 0),
+            ]
+        );
+    }
+    #[test]
+    fn test_summary_line() {
+        let store = TicketStore::new();
+        assert_eq!(
+            store.summary_line(),
+            "0 tickets: 0 todo, 0 in progress, 0 done"
+        );
+
+        store.insert(get_ticket()).unwrap();
+        assert_eq!(
+            store.summary_line(),
+            "1 ticket: 1 todo, 0 in progress, 0 done"
+        );
+
+        let statuses = [
+            TicketStatus::InProgress,
+            TicketStatus::Done,
+            TicketStatus::Done,
+        ];
+        for (id, status) in (1..).zip(statuses) {
+            let mut ticket = get_ticket();
+            ticket.id = TicketId::from(id);
+            ticket.status = status;
+            store.insert(ticket).unwrap();
+        }
+        assert_eq!(
+            store.summary_line(),
+            "4 tickets: 1 todo, 1 in progress, 2 done"
+        );
+
+        let blocked = CustomStatus::new("blocked").unwrap();
+        let store = TicketStore::new().with_custom_statuses([blocked]);
+        let mut ticket = get_ticket();
+        ticket.status = TicketStatus::Custom(blocked);
+        store.insert(ticket).unwrap();
+        assert_eq!(
+            store.summary_line(),
+            "1 ticket: 0 todo, 0 in progress, 0 done, 1 blocked"
+        );
+    }
+    #[test]
+    fn test_custom_statuses() {
+        let blocked = TicketStatus::Custom(CustomStatus::new("blocked").unwrap());
+        let review = TicketStatus::Custom(CustomStatus::new("review").unwrap());
+        let unknown = CustomStatus::new("limbo").unwrap();
+        let store = TicketStore::new().with_custom_statuses([
+            CustomStatus::new("blocked").unwrap(),
+            CustomStatus::new("review").unwrap(),
+        ]);
+
+        let mut ticket = get_ticket();
+        ticket.status = blocked;
+        store.insert(ticket.clone()).unwrap();
+        assert_eq!(store.get(ticket.id).unwrap().status, blocked);
+
+        let patch = TicketPatch::new(ticket.id, None, None, Some(review));
+        store.patch(ticket.id, patch).unwrap();
+        assert_eq!(store.get(ticket.id).unwrap().status, review);
+        let patch = TicketPatch::new(ticket.id, None, None, Some(TicketStatus::Done));
+        store.patch(ticket.id, patch).unwrap();
+
+        let patch = TicketPatch::new(ticket.id, None, None, Some(TicketStatus::Custom(unknown)));
+        assert_eq!(
+            store.patch(ticket.id, patch),
+            Err(PatchError::UnknownStatus(unknown))
+        );
+        let mut other = get_ticket();
+        other.id = 43.into();
+        other.status = TicketStatus::Custom(unknown);
+        assert_eq!(
+            store.insert(other),
+            Err(InsertError::UnknownStatus(unknown))
+        );
+        // Built-in statuses need no registration.
+        assert_eq!(TicketStore::new().insert(get_ticket()), Ok(()));
+    }
+    #[test]
+    fn test_status_transitions() {
+        let blocked = TicketStatus::Custom(CustomStatus::new("blocked").unwrap());
+        let store = TicketStore::new()
+            .with_custom_statuses([CustomStatus::new("blocked").unwrap()])
+            .with_transitions(BTreeMap::from([(
+                blocked,
+                BTreeSet::from([TicketStatus::InProgress]),
+            )]));
+        let mut ticket = get_ticket();
+        ticket.status = blocked;
+        store.insert(ticket.clone()).unwrap();
+
+        let patch = TicketPatch::new(ticket.id, None, None, Some(TicketStatus::Done));
+        assert_eq!(
+            store.patch(ticket.id, patch),
+            Err(PatchError::Transition {
+                from: blocked,
+                to: TicketStatus::Done
+            })
+        );
+        assert_eq!(store.get(ticket.id).unwrap().status, blocked);
+        let patch = TicketPatch::new(ticket.id, None, None, Some(TicketStatus::InProgress));
+        store.patch(ticket.id, patch).unwrap();
+        // `InProgress` has no rule, so anything goes from there.
+        let patch = TicketPatch::new(ticket.id, None, None, Some(blocked));
+        store.patch(ticket.id, patch).unwrap();
+    }
+    #[test]
+    fn test_by_insertion_order() {
+        let store = TicketStore::new();
+        for id in [5, 1, 9, 3] {
+            let mut ticket = get_ticket();
+            ticket.id = id.into();
+            store.insert(ticket).unwrap();
+        }
+        let mut again = get_ticket();
+        again.id = 1.into();
+        store.insert(again).unwrap();
+        let patch = TicketPatch::new(9.into(), None, None, Some(TicketStatus::Done));
+        store.patch(9.into(), patch).unwrap();
+        store.delete_by_status(TicketStatus::Done);
+
+        let ids: Vec<TicketId> = store
+            .by_insertion_order()
+            .iter()
+            .map(|ticket| ticket.id)
+            .collect();
+        assert_eq!(ids, vec![5.into(), 3.into(), 1.into()]);
+    }
+    #[test]
+    fn test_patch_outcome() {
+        let store = TicketStore::new();
+        let mut events = store.subscribe();
+        let ticket = get_ticket();
+        store.insert(ticket.clone()).unwrap();
+        events.try_recv().unwrap();
+
+        let same = TicketPatch::new(
+            ticket.id,
+            Some(ticket.title.clone()),
+            None,
+            Some(ticket.status),
+        );
+        assert_eq!(store.patch(ticket.id, same), Ok(PatchOutcome::Unchanged));
+        assert_eq!(store.get(ticket.id).unwrap().updated_at, ticket.updated_at);
+        assert!(events.try_recv().is_err());
+
+        let done = TicketPatch::new(ticket.id, None, None, Some(TicketStatus::Done));
+        assert_eq!(store.patch(ticket.id, done), Ok(PatchOutcome::Changed));
+        assert!(matches!(events.try_recv(), Ok(TicketEvent::Updated(_))));
+
+        // A no-op with the wrong id is still a mismatch.
+        let mismatched = TicketPatch::new(7.into(), None, None, None);
+        assert!(matches!(
+            store.patch(ticket.id, mismatched),
+            Err(PatchError::Mismatch(_))
+        ));
+    }
+    #[test]
+    fn test_with_start_id() {
+        let store = TicketStore::new().with_start_id(1000);
+        let ticket = get_ticket();
+        store.insert(ticket.clone()).unwrap();
+
+        assert_eq!(store.duplicate(ticket.id), Some(1000.into()));
+        assert_eq!(store.duplicate(ticket.id), Some(1001.into()));
+    }
+    #[test]
+    fn test_import_csv() {
+        let store = TicketStore::new();
+        let input = "id,status,title,description\n\
+                     1,todo,\"a title, with a comma\",a description\n\
+                     2,nonsense,a title,a description\n";
+
+        let summary = store.import_csv(input, false);
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.rejected.len(), 1);
+        assert_eq!(summary.rejected[0].row, 3);
+        assert!(summary.rejected[0].reason.contains("nonsense"));
+        let imported = store.get(1.into()).unwrap();
+        assert_eq!(imported.title.as_str(), "a title, with a comma");
+        assert_eq!(store.get(2.into()), None);
+    }
+    #[test]
+    fn test_import_csv_strict() {
+        let store = TicketStore::new();
+        let input = "1,todo,a title,a description\n2,done,,a description\n";
+
+        let summary = store.import_csv(input, true);
+        assert_eq!(summary.imported, 0);
+        assert_eq!(summary.rejected.len(), 1);
+        assert_eq!(summary.rejected[0].row, 2);
+        assert!(store.list().is_empty());
+    }
+    #[test]
+    fn test_import_csv_round_trip() {
+        let store = TicketStore::new();
+        store.insert(get_ticket()).unwrap();
+        let other = TicketStore::new();
+        let summary = other.import_csv(&store.to_csv(), true);
+        assert_eq!(summary.imported, 1);
+        assert_eq!(other.to_csv(), store.to_csv());
+    }
+    #[test]
+    fn test_import_csv_round_trip_custom_status() {
+        let blocked = CustomStatus::new("blocked").unwrap();
+        let store = TicketStore::new().with_custom_statuses([blocked]);
+        let mut ticket = get_ticket();
+        ticket.status = TicketStatus::Custom(blocked);
+        store.insert(ticket).unwrap();
+        let csv = store.to_csv();
+
+        let other = TicketStore::new().with_custom_statuses([blocked]);
+        assert_eq!(other.import_csv(&csv, true).imported, 1);
+        assert_eq!(other.to_csv(), csv);
+        // A store that never registered `blocked` can't read it back.
+        let summary = TicketStore::new().import_csv(&csv, true);
+        assert_eq!(summary.imported, 0);
+        assert!(summary.rejected[0].reason.contains("blocked"));
+    }
+    #[test]
+    fn test_parse_status() {
+        let blocked = CustomStatus::new("blocked").unwrap();
+        let store = TicketStore::new().with_custom_statuses([blocked]);
+        assert_eq!(store.parse_status("done"), Ok(TicketStatus::Done));
+        assert_eq!(
+            store.parse_status("blocked"),
+            Ok(TicketStatus::Custom(blocked))
+        );
+        assert_eq!(
+            store.parse_status("limbo"),
+            Err(TicketParseError::StatusParseError("limbo".to_string()))
+        );
+    }
+    #[test]
+    fn test_delete_by_status() {
+        let store = TicketStore::new();
+        for (id, status) in [(1, "done"), (2, "todo"), (3, "done"), (4, "inprogress")] {
+            let mut ticket = get_ticket();
+            ticket.id = id.into();
+            ticket.status = status.try_into().unwrap();
+            store.insert(ticket).unwrap();
+        }
+
+        assert_eq!(store.delete_by_status(TicketStatus::Done), 2);
+        let remaining: Vec<TicketId> = store.list().iter().map(|ticket| ticket.id).collect();
+        assert_eq!(remaining, vec![2.into(), 4.into()]);
+        assert_eq!(store.delete_by_status(TicketStatus::Done), 0);
+    }
+    #[test]
+    fn test_slow_subscriber_lags() {
+        let store = TicketStore::with_event_capacity(2);
+        let mut events = store.subscribe();
+        for id in 0..5 {
+            let mut ticket = get_ticket();
+            ticket.id = id.into();
+            store.insert(ticket).unwrap();
+        }
+
+        assert_eq!(
+            events.try_recv(),
+            Err(broadcast::error::TryRecvError::Lagged(3))
+        );
+        let TicketEvent::Created(ticket) = events.try_recv().unwrap() else {
+            panic!("expected a created event");
+        };
+        assert_eq!(ticket.id, 3.into());
+    }
+    #[test]
+    fn test_to_csv() {
+        let store = TicketStore::new();
+        store.insert(get_ticket()).unwrap();
+        store
+            .insert(Ticket::new(
+                43.into(),
+                "fix login, then logout".try_into().unwrap(),
+                "the \"remember me\" box".try_into().unwrap(),
+                "inprogress".try_into().unwrap(),
+            ))
+            .unwrap();
+
+        let csv = store.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("id,status,title,description"));
+        assert_eq!(
+            lines.next(),
+            Some("42,todo,this is a title,this is a description")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("43,inprogress,\"fix login, then logout\",\"the \"\"remember me\"\" box\"")
+        );
+        assert_eq!(lines.next(), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_list_is_consistent_under_concurrent_writes() {
+        fn generation(id: u64, n: u32) -> TicketPatch {
+            TicketPatch::new(
+                id.into(),
+                Some(format!("gen {n}").try_into().unwrap()),
+                Some(format!("gen {n}").try_into().unwrap()),
+                None,
+            )
+        }
+
+        let store = TicketStore::new();
+        for id in 0..20 {
+            let mut ticket = get_ticket();
+            ticket.id = id.into();
+            ticket.title = "gen 0".try_into().unwrap();
+            ticket.description = "gen 0".try_into().unwrap();
+            store.insert(ticket).unwrap();
+        }
+
+        let writer = {
+            let store = store.clone();
+            tokio::spawn(async move {
+                for n in 1..=500 {
+                    let patches = (0..20).map(|id| generation(id, n)).collect();
+                    store.patch_many(patches);
+                    tokio::task::yield_now().await;
+                }
+            })
+        };
+
+        while !writer.is_finished() {
+            let listing = store.list();
+            assert_eq!(listing.len(), 20);
+            let first = listing[0].title.clone();
+            for ticket in &listing {
+                assert_eq!(ticket.title, first);
+                assert_eq!(ticket.description.as_str(), ticket.title.as_str());
+            }
+            tokio::task::yield_now().await;
+        }
+        writer.await.unwrap();
+        assert!(store
+            .list()
+            .iter()
+            .all(|ticket| ticket.title.as_str() == "gen 500"));
+    }
+
+    #[tokio::test]
+    async fn test_compare_and_set_rejects_stale_reads() {
+        let store = TicketStore::new();
+        store.insert(get_ticket()).unwrap();
+        let read = store.get(42.into()).unwrap();
+
+        // Someone else edits the ticket after our read.
+        let patch = TicketPatch::new(42.into(), None, None, Some(TicketStatus::InProgress));
+        store.patch(42.into(), patch).unwrap();
+
+        let mut edit = Ticket::clone(&read);
+        edit.title = "mine".try_into().unwrap();
+        assert_eq!(
+            store.compare_and_set(42.into(), &read, edit.clone()),
+            Err(CasError::Stale(42.into()))
+        );
+        assert_eq!(store.get(42.into()).unwrap().title, read.title);
+
+        let fresh = store.get(42.into()).unwrap();
+        edit.status = fresh.status;
+        store.compare_and_set(42.into(), &fresh, edit).unwrap();
+        let stored = store.get(42.into()).unwrap();
+        assert_eq!(stored.title.as_str(), "mine");
+        assert_eq!(stored.status, TicketStatus::InProgress);
+        assert_eq!(stored.created_at, fresh.created_at);
+
+        assert_eq!(
+            store.compare_and_set(7.into(), &fresh, get_ticket()),
+            Err(CasError::Patch(PatchError::Mismatch(
+                ticket::TicketUpdateError {
+                    original_id: 7.into(),
+                    patch_id: 42.into(),
+                }
+            )))
+        );
+    }
 }